@@ -5,9 +5,19 @@ fn main() {
     test("source/or_not.txt", path(Or, Not));
     test("source/and_true.txt", app(And, true));
     test("source/and_false.txt", app(And, false));
+    test_knowledge("source/rules.txt", vec![
+        Red(path(And, Not), Or.into()),
+        Red(path(Or, Not), And.into()),
+        Eqv(comp(Not, And), Nand.into()),
+    ]);
 }
 
 fn test(file: &str, e: Expr) {
     let a: Expr = parse(file).map_err(|err| panic!("{}", err)).unwrap();
     assert_eq!(a, e);
 }
+
+fn test_knowledge(file: &str, k: Vec<Knowledge>) {
+    let a: Vec<Knowledge> = parse_knowledge(file).map_err(|err| panic!("{}", err)).unwrap();
+    assert_eq!(a, k);
+}
@@ -1,27 +1,57 @@
 use poi::*;
 
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::validate::Validator;
+use rustyline::{Editor, Helper};
+
 fn main() {
     println!("=== Poi Reduce 0.4 ===");
     println!("Type `help` for more information.");
-    let ref std = std();
+    let mut session = Session::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (&*arg, args.next()) {
+            ("--load", Some(file)) => session.load_and_report(&file),
+            _ => {
+                println!("ERROR: Unknown argument `{}`, expected `--load <file>`", arg);
+                return;
+            }
+        }
+    }
+
+    let mut rl: Editor<PoiHelper, _> = match Editor::new() {
+        Ok(rl) => rl,
+        Err(err) => {
+            println!("ERROR: Could not start line editor, {}", err);
+            return;
+        }
+    };
+    rl.set_helper(Some(PoiHelper {defs: def_names(&session.knowledge)}));
+    let history = history_file();
+    if let Some(history) = &history {
+        // The history file does not exist the first time.
+        let _ = rl.load_history(history);
+    }
 
-    let mut prev_expr: Option<Expr> = None;
+    let mut tree = ProofTree::new();
+    let mut strategy = Strategy::Standard;
     loop {
-        use std::io::{self, Write};
-
-        print!("> ");
-        let mut input = String::new();
-        io::stdout().flush().unwrap();
-        match io::stdin().read_line(&mut input) {
-            Ok(_) => {}
-            Err(_) => {
-                println!("ERROR: Could not read input");
+        let input = match rl.readline("> ") {
+            Ok(input) => input,
+            Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => break,
+            Err(err) => {
+                println!("ERROR: Could not read input, {}", err);
                 continue;
             }
         };
+        if !input.trim().is_empty() {
+            let _ = rl.add_history_entry(input.trim());
+        }
 
-        let mut inlined = false;
-        match input.trim() {
+        let (expr, parent, via) = match input.trim() {
             "" => {
                 // Print separator for readability.
                 print!("\n------------------------------------<o=o");
@@ -34,19 +64,66 @@ fn main() {
             "help dom" => {print_help_dom(); continue}
             "help triv" => {print_help_triv(); continue}
             "help ex" => {print_help_ex(); continue}
-            "std" => {for k in std {println!("{}", k)}; continue}
+            "help proof" => {print_help_proof(); continue}
+            "help load" => {print_help_load(); continue}
+            "help strategy" => {print_help_strategy(); continue}
+            "help tactic" => {print_help_tactic(); continue}
+            "std" => {
+                for source in &session.sources {
+                    if source.knowledge.is_empty() {continue};
+                    println!("// {}", source.name);
+                    for k in &source.knowledge {println!("{}", k)}
+                }
+                continue;
+            }
+            "strategy" => {
+                println!("{}", strategy_name(strategy));
+                continue;
+            }
+            "reload" => {
+                session.reload();
+                rl.helper_mut().unwrap().defs = def_names(&session.knowledge);
+                continue;
+            }
+            "history" => {
+                if let Some(id) = tree.current {
+                    tree.print_history(id);
+                } else {
+                    println!("ERROR: No previous expression");
+                }
+                continue;
+            }
+            "positions" => {
+                if let Some(id) = tree.current {
+                    let expr = &tree.nodes[id].expr;
+                    for pos in expr.positions() {
+                        println!("{}\t{}", pos, expr.get(&pos).unwrap());
+                    }
+                } else {
+                    println!("ERROR: No previous expression");
+                }
+                continue;
+            }
+            "back" | "undo" => {
+                match tree.current.map(|id| tree.nodes[id].parent) {
+                    Some(Some(parent)) => {
+                        tree.current = Some(parent);
+                        tree.print_node(parent);
+                    }
+                    Some(None) => println!("ERROR: Already at the start of the proof"),
+                    None => println!("ERROR: No previous expression"),
+                }
+                continue;
+            }
             "inline all" => {
-                if let Some(expr) = &prev_expr {
-                    prev_expr = Some(match expr.inline_all(std) {
-                        Ok(x) => {
-                            inlined = true;
-                            x
-                        }
+                if let Some(id) = tree.current {
+                    match tree.nodes[id].expr.inline_all(&session.knowledge) {
+                        Ok(x) => (x, Some(id), Via::InlineAll),
                         Err(err) => {
                             println!("ERROR: {:?}", err);
                             continue;
                         }
-                    });
+                    }
                 } else {
                     println!("ERROR: No previous expression");
                     continue;
@@ -54,17 +131,90 @@ fn main() {
             }
             "bye" => break,
             x => {
-                // Print definitions of symbol.
-                if x.starts_with("def ") {
-                    match parse_str(x[4..].trim()) {
+                // Define a symbol in the session.
+                if let Some(def) = x.strip_prefix("def ").filter(|def| def.contains(":=")) {
+                    match parse_rule(def, "a definition `a := b`", |k| matches!(k, Knowledge::Def(..))) {
+                        Ok(k) => session.add(k),
+                        Err(err) => println!("ERROR:\n{}", err),
+                    }
+                    rl.helper_mut().unwrap().defs = def_names(&session.knowledge);
+                    continue;
+                } else if let Some(rule) = x.strip_prefix("rule ") {
+                    match parse_rule(rule, "a rule", |_| true) {
+                        Ok(k) => session.add(k),
+                        Err(err) => println!("ERROR:\n{}", err),
+                    }
+                    rl.helper_mut().unwrap().defs = def_names(&session.knowledge);
+                    continue;
+                } else if let Some(eqv) = x.strip_prefix("eqv ").filter(|eqv| eqv.contains("<=>")) {
+                    match parse_rule(eqv, "an equivalence `a <=> b`", |k| matches!(k, Knowledge::Eqv(..))) {
+                        Ok(k) => session.add(k),
+                        Err(err) => println!("ERROR:\n{}", err),
+                    }
+                    continue;
+                } else if let Some(rule) = x.strip_prefix("why not ") {
+                    let id = if let Some(id) = tree.current {id} else {
+                        println!("ERROR: No previous expression");
+                        continue;
+                    };
+                    let rule = rule.trim();
+                    let i = match rule.parse::<usize>() {
+                        Ok(i) if i < session.knowledge.len() => i,
+                        Ok(_) => {
+                            println!("ERROR: There is no rule with that number");
+                            continue;
+                        }
+                        Err(_) => match parse_rule(rule, "a rule", |_| true) {
+                            Ok(k) => match session.knowledge.iter().position(|n| n == &k) {
+                                Some(i) => i,
+                                None => {
+                                    println!("ERROR: Could not find `{}`, see `std`", k);
+                                    continue;
+                                }
+                            }
+                            Err(err) => {
+                                println!("ERROR:\n{}", err);
+                                continue;
+                            }
+                        }
+                    };
+                    let expr = &tree.nodes[id].expr;
+                    println!("{}\t\t( {} )", expr, session.knowledge[i]);
+                    match expr.why_not(&session.knowledge, i) {
+                        None => println!("(the rule applies)"),
+                        Some(why_not) => println!("{}", why_not),
+                    }
+                    continue;
+                } else if let Some(name) = x.strip_prefix("strategy ") {
+                    match STRATEGIES.iter().find(|(n, _)| *n == name.trim()) {
+                        Some((_, s)) => strategy = *s,
+                        None => println!("ERROR: Unknown strategy `{}`, see `help strategy`", name.trim()),
+                    }
+                    continue;
+                } else if let Some(file) = x.strip_prefix("save ") {
+                    match session.save(file.trim()) {
+                        Ok(n) => println!("Saved {} rules to `{}`", n, file.trim()),
+                        Err(err) => println!("ERROR: {}", err),
+                    }
+                    continue;
+                } else if let Some(sym) = x.strip_prefix("def ") {
+                    // Print definitions of symbol.
+                    match parse_str(sym.trim()) {
                         Ok(Expr::Sym(s)) => {
                             let mut found = false;
-                            for k in std.iter() {
-                                if let Knowledge::Def(a, b) = k {
-                                    if a == &s {
-                                        found = true;
-                                        println!("{}", b);
-                                    };
+                            for source in &session.sources {
+                                let mut header = false;
+                                for k in &source.knowledge {
+                                    if let Knowledge::Def(a, b) = k {
+                                        if a == &s {
+                                            if !header {
+                                                println!("// {}", source.name);
+                                                header = true;
+                                            }
+                                            found = true;
+                                            println!("{}", b);
+                                        };
+                                    }
                                 }
                             }
                             if !found {println!("(no definition found)")};
@@ -79,37 +229,489 @@ fn main() {
                             continue;
                         }
                     }
+                } else if let Some(n) = x.strip_prefix("eqv ") {
+                    let id = if let Some(id) = tree.current {id} else {
+                        println!("ERROR: No previous expression");
+                        continue;
+                    };
+                    let equivalences = &tree.nodes[id].equivalences;
+                    match n.trim().parse::<usize>() {
+                        Ok(i) if i < equivalences.len() => {
+                            let (expr, j) = equivalences[i].clone();
+                            (expr, Some(id), Via::Eqv(j))
+                        }
+                        Ok(_) => {
+                            println!("ERROR: There is no equivalence with that number");
+                            continue;
+                        }
+                        Err(_) => {
+                            println!("ERROR: Expected number, e.g. `eqv 0`");
+                            continue;
+                        }
+                    }
+                } else if let Some(script) = x.strip_prefix("tactic ") {
+                    let id = if let Some(id) = tree.current {id} else {
+                        println!("ERROR: No previous expression");
+                        continue;
+                    };
+                    let tactic = match parse_tactic_str(script) {
+                        Ok(tactic) => tactic,
+                        Err(err) => {
+                            println!("ERROR:\n{}", err);
+                            continue;
+                        }
+                    };
+                    match tactic.run(&tree.nodes[id].expr, &session.knowledge) {
+                        Ok(trace) => {
+                            print!("{}", trace);
+                            (trace.result().clone(), Some(id), Via::Tactic(script.trim().into()))
+                        }
+                        Err(err) => {
+                            println!("ERROR: {:?}", err);
+                            continue;
+                        }
+                    }
+                } else if let Some(query) = x.strip_prefix("solve ") {
+                    match parse_query_str(query) {
+                        Ok((a, b)) => {
+                            let solutions = solve(&a, &b, &session.knowledge, SOLVE_LIMIT);
+                            if solutions.is_empty() {println!("(no solution found)")};
+                            for solution in solutions.iter().take(10) {
+                                let solution: Vec<String> = solution.iter()
+                                    .map(|(x, a)| format!("?{} = {}", x, a)).collect();
+                                println!("{}", if solution.is_empty() {"(true)".into()}
+                                               else {solution.join(", ")});
+                            }
+                        }
+                        Err(err) => println!("ERROR:\n{}", err),
+                    }
+                    continue;
+                } else if let Some(file) = x.strip_prefix("export ") {
+                    match tree.export(file.trim()) {
+                        Ok(()) => println!("Exported proof tree to `{}`", file.trim()),
+                        Err(err) => println!("ERROR: {}", err),
+                    }
+                    continue;
+                } else if let Some(file) = x.strip_prefix("load ") {
+                    session.load_and_report(file.trim());
+                    rl.helper_mut().unwrap().defs = def_names(&session.knowledge);
+                    continue;
+                } else if let Some(file) = x.strip_prefix("unload ") {
+                    match session.unload(file.trim()) {
+                        Ok(()) => println!("Unloaded `{}`", file.trim()),
+                        Err(err) => {
+                            println!("ERROR: {}", err);
+                            println!("(no rules were changed)");
+                        }
+                    }
+                    rl.helper_mut().unwrap().defs = def_names(&session.knowledge);
+                    continue;
+                } else {
+                    match parse_str(x) {
+                        Ok(expr) => (expr, None, Via::Input),
+                        Err(err) => {
+                            println!("ERROR:\n{}", err);
+                            continue;
+                        }
+                    }
                 }
             }
+        };
+
+        let id = tree.push(expr, parent, via, &session.knowledge, strategy);
+        tree.print_node(id);
+    }
+
+    if let Some(history) = &history {
+        if let Err(err) = rl.save_history(history) {
+            println!("ERROR: Could not save history, {}", err);
         }
+    }
+}
 
-        let mut expr = if inlined {prev_expr.unwrap()} else {
-                match parse_str(&input) {
-                    Ok(expr) => expr,
-                    Err(err) => {
-                        println!("ERROR:\n{}", err);
-                        continue;
-                    }
+/// REPL commands, used for tab completion.
+const COMMANDS: &[&str] = &[
+    "bye", "inline all", "eqv ", "back", "undo", "history", "positions", "export ", "def ", "std",
+    "load ", "unload ", "reload", "rule ", "save ", "why not ", "tactic ", "solve ", "strategy",
+    "strategy standard", "strategy innermost", "strategy outermost", "strategy parallel",
+    "strategy priority", "help strategy", "help tactic",
+    "help", "help asym", "help eqv", "help dom", "help triv", "help ex", "help proof",
+    "help load",
+];
+
+/// Names of reduction strategies.
+const STRATEGIES: &[(&str, Strategy)] = &[
+    ("standard", Strategy::Standard),
+    ("innermost", Strategy::LeftmostInnermost),
+    ("outermost", Strategy::LeftmostOutermost),
+    ("parallel", Strategy::ParallelOutermost),
+    ("priority", Strategy::RulePriority),
+];
+
+fn strategy_name(strategy: Strategy) -> &'static str {
+    STRATEGIES.iter().find(|(_, s)| *s == strategy).unwrap().0
+}
+
+/// Parses a single rule of the expected kind.
+fn parse_rule(
+    data: &str,
+    expected: &str,
+    f: impl Fn(&Knowledge) -> bool
+) -> Result<Knowledge, String> {
+    let mut knowledge = parse_knowledge_str(data)?;
+    if knowledge.len() == 1 && f(&knowledge[0]) {
+        Ok(knowledge.remove(0))
+    } else {
+        Err(format!("Expected {}", expected))
+    }
+}
+
+/// Returns the file where the line editor history is stored across sessions.
+fn history_file() -> Option<std::path::PathBuf> {
+    std::env::var_os("HOME").map(|home| std::path::Path::new(&home).join(".poireduce_history"))
+}
+
+/// Returns the names of user defined symbols.
+fn def_names(knowledge: &[Knowledge]) -> Vec<String> {
+    let mut res = vec![];
+    for k in knowledge {
+        if let Knowledge::Def(Symbol::Var(name), _) = k {
+            res.push((**name).clone());
+        }
+    }
+    res
+}
+
+/// Completes commands, built-in symbols and user defined symbols.
+struct PoiHelper {
+    defs: Vec<String>,
+}
+
+impl Completer for PoiHelper {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let line = &line[..pos];
+        let pair = |s: &str| Pair {display: s.trim().into(), replacement: s.into()};
+
+        // Commands are completed from the start of the line.
+        let commands: Vec<Pair> = COMMANDS.iter()
+            .filter(|cmd| cmd.starts_with(line) && **cmd != line)
+            .map(|cmd| pair(cmd))
+            .collect();
+
+        let start = line.rfind(|c: char| c.is_whitespace() || "[]():,.·⨯-→{}".contains(c))
+            .map(|i| i + line[i..].chars().next().unwrap().len_utf8())
+            .unwrap_or(0);
+        // Commands with several words, e.g. `help eqv`, are completed without symbols.
+        if start > 0 && !commands.is_empty() {return Ok((0, commands))};
+        let word = &line[start..];
+        if word.is_empty() {return Ok((pos, commands))};
+        let mut symbols: Vec<Pair> = SYMBOLS.iter()
+            .map(|(name, _)| *name)
+            .chain(self.defs.iter().map(|name| &**name))
+            .filter(|name| name.starts_with(word))
+            .map(pair)
+            .collect();
+        // At the start of the line, both commands and symbols are candidates.
+        if start == 0 {symbols.extend(commands)};
+        symbols.sort_by(|a, b| a.display.cmp(&b.display));
+        symbols.dedup_by(|a, b| a.display == b.display);
+        Ok((start, symbols))
+    }
+}
+
+impl Hinter for PoiHelper {
+    type Hint = String;
+}
+
+impl Highlighter for PoiHelper {}
+
+impl Validator for PoiHelper {}
+
+impl Helper for PoiHelper {}
+
+/// Rules from the session, a knowledge file or the standard library.
+struct Source {
+    name: String,
+    knowledge: Vec<Knowledge>,
+}
+
+/// Keeps track of the knowledge used in a session.
+///
+/// Rules defined in the session come first, followed by knowledge files
+/// and then the standard library, such that they extend or shadow it.
+struct Session {
+    /// Rules defined in the session, followed by loaded knowledge files
+    /// in load order, followed by the standard library.
+    sources: Vec<Source>,
+    /// All rules in the session.
+    knowledge: Vec<Knowledge>,
+}
+
+impl Session {
+    fn new() -> Session {
+        let mut session = Session {
+            sources: vec![
+                Source {name: "session".into(), knowledge: vec![]},
+                Source {name: "std".into(), knowledge: std()},
+            ],
+            knowledge: vec![],
+        };
+        session.update();
+        session
+    }
+
+    /// Collects all rules from the sources.
+    fn update(&mut self) {
+        self.knowledge = self.sources.iter().flat_map(|s| s.knowledge.iter().cloned()).collect();
+    }
+
+    /// Loads a knowledge file.
+    ///
+    /// If the file is already loaded, its rules are replaced.
+    fn load(&mut self, file: &str) -> Result<usize, String> {
+        let knowledge = parse_knowledge(file)?;
+        let n = knowledge.len();
+        if let Some(source) = self.sources.iter_mut().find(|s| s.name == file) {
+            source.knowledge = knowledge;
+        } else {
+            let i = self.sources.len() - 1;
+            self.sources.insert(i, Source {name: file.into(), knowledge});
+        }
+        self.update();
+        Ok(n)
+    }
+
+    fn load_and_report(&mut self, file: &str) {
+        match self.load(file) {
+            Ok(n) => println!("Loaded {} rules from `{}`", n, file),
+            Err(err) => {
+                println!("ERROR:\n{}", err);
+                println!("(no rules were changed)");
+            }
+        }
+    }
+
+    /// Unloads a knowledge file.
+    fn unload(&mut self, file: &str) -> Result<(), String> {
+        let i = self.sources.iter().position(|s| s.name == file)
+            .filter(|&i| i > 0 && i + 1 < self.sources.len())
+            .ok_or_else(|| format!("`{}` is not loaded", file))?;
+        self.sources.remove(i);
+        self.update();
+        Ok(())
+    }
+
+    /// Reloads all knowledge files from disk.
+    ///
+    /// If any file fails to parse, all previous rules are kept.
+    fn reload(&mut self) {
+        let n = self.sources.len();
+        if n == 2 {println!("(no knowledge files loaded)")};
+        let mut loaded = vec![];
+        for source in &self.sources[1..n - 1] {
+            match parse_knowledge(&source.name) {
+                Ok(knowledge) => loaded.push(knowledge),
+                Err(err) => {
+                    println!("ERROR:\n{}", err);
+                    println!("(no rules were changed)");
+                    return;
                 }
-            };
-        println!("{}", expr);
-        loop {
-            if let Ok((nexpr, i)) = expr.reduce(std) {
-                if nexpr == expr {break};
+            }
+        }
+        for (source, knowledge) in self.sources[1..n - 1].iter_mut().zip(loaded) {
+            println!("Loaded {} rules from `{}`", knowledge.len(), source.name);
+            source.knowledge = knowledge;
+        }
+        self.update();
+    }
+
+    /// Adds a rule to the session.
+    fn add(&mut self, k: Knowledge) {
+        println!("Added `{}`", k);
+        self.sources[0].knowledge.push(k);
+        self.update();
+    }
+
+    /// Writes the rules defined in the session to a knowledge file.
+    fn save(&self, file: &str) -> Result<usize, String> {
+        use std::fs::File;
+        use std::io::Write;
+
+        let mut w = File::create(file).map_err(|err|
+            format!("Could not create `{}`, {}", file, err))?;
+        let knowledge = &self.sources[0].knowledge;
+        for k in knowledge {
+            writeln!(w, "{}", k).map_err(|err|
+                format!("Could not write to `{}`, {}", file, err))?;
+        }
+        Ok(knowledge.len())
+    }
+}
+
+/// Describes how a proof step was reached from its parent.
+enum Via {
+    /// The expression was typed in by the user.
+    Input,
+    /// An equivalence was picked using `eqv <number>`.
+    Eqv(Knowledge),
+    /// All definitions were inlined using `inline all`.
+    InlineAll,
+    /// A tactic script was run using `tactic <script>`.
+    Tactic(String),
+}
+
+/// A proof step, consisting of a start expression and its reductions.
+struct Node {
+    /// The expression at the start of the step.
+    start: Expr,
+    /// How the step was reached.
+    via: Via,
+    /// The reductions applied to the start expression.
+    reductions: Vec<(Expr, Knowledge)>,
+    /// The reduced expression.
+    expr: Expr,
+    /// The equivalences of the reduced expression.
+    equivalences: Vec<(Expr, Knowledge)>,
+    /// The previous step.
+    parent: Option<usize>,
+    /// The next steps that have been taken from this one.
+    children: Vec<usize>,
+}
+
+/// Keeps track of the proof steps taken in a session.
+///
+/// Every expression typed in starts a new proof,
+/// while picking an equivalence continues the current proof.
+struct ProofTree {
+    nodes: Vec<Node>,
+    current: Option<usize>,
+}
+
+impl ProofTree {
+    fn new() -> ProofTree {
+        ProofTree {nodes: vec![], current: None}
+    }
+
+    /// Reduces an expression and adds it as a new step.
+    ///
+    /// Rules are copied into the step, such that the proof tree stays
+    /// valid when the knowledge of the session changes.
+    fn push(
+        &mut self,
+        start: Expr,
+        parent: Option<usize>,
+        via: Via,
+        std: &[Knowledge],
+        strategy: Strategy
+    ) -> usize {
+        let mut expr = start.clone();
+        let mut reductions = vec![];
+        while let Ok((nexpr, i)) = expr.reduce_with(std, strategy) {
+            if nexpr == expr {break};
+            expr = nexpr;
+            reductions.push((expr.clone(), std[i].clone()));
+        }
+        if let Via::InlineAll = via {
+            // Fold `if` trees back into the boolean symbols they define.
+            while let Some((nexpr, def)) = expr.fold_boolean(std) {
                 expr = nexpr;
-                println!("{}\t\t\t( {} )", expr, std[i]);
-            } else {
-                break;
+                reductions.push((expr.clone(), def));
+            }
+        }
+        let equivalences = expr.equivalences(std).into_iter()
+            .map(|(expr, i)| (expr, std[i].clone())).collect();
+
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            start,
+            via,
+            reductions,
+            expr,
+            equivalences,
+            parent,
+            children: vec![],
+        });
+        if let Some(parent) = parent {self.nodes[parent].children.push(id)};
+        self.current = Some(id);
+        id
+    }
+
+    /// Prints a step with its numbered equivalences.
+    fn print_node(&self, id: usize) {
+        let node = &self.nodes[id];
+        println!("{}", node.start);
+        for (expr, k) in &node.reductions {
+            println!("{}\t\t\t( {} )", expr, k);
+        }
+        for (i, (expr, k)) in node.equivalences.iter().enumerate() {
+            println!("<=> {}  {}\t\t( {} )", i, expr, k);
+        }
+    }
+
+    /// Prints the path taken from the start of the proof to a step.
+    fn print_history(&self, id: usize) {
+        let mut path = vec![id];
+        while let Some(parent) = self.nodes[*path.last().unwrap()].parent {
+            path.push(parent);
+        }
+        for &id in path.iter().rev() {
+            let node = &self.nodes[id];
+            match &node.via {
+                Via::Input => println!("{}", node.start),
+                Via::Eqv(k) => println!("<=>  {}\t\t( {} )", node.start, k),
+                Via::InlineAll => println!("{}\t\t( inline all )", node.start),
+                Via::Tactic(script) => println!("{}\t\t( tactic {} )", node.start, script),
+            }
+            if let Some((expr, _)) = node.reductions.last() {
+                println!("=>   {}\t\t( {} reductions )", expr, node.reductions.len());
             }
         }
+    }
+
+    /// Writes the proof tree of the session to a file.
+    fn export(&self, file: &str) -> Result<(), String> {
+        use std::fs::File;
 
-        let equivalences = expr.equivalences(std);
-        for i in 0..equivalences.len() {
-            let j = equivalences[i].1;
-            println!("<=>  {}\t\t( {} )", equivalences[i].0, std[j]);
+        let mut w = File::create(file).map_err(|err|
+            format!("Could not create `{}`, {}", file, err))?;
+        for id in 0..self.nodes.len() {
+            if self.nodes[id].parent.is_none() {
+                self.write_node(&mut w, id, 0).map_err(|err|
+                    format!("Could not write to `{}`, {}", file, err))?;
+            }
         }
+        Ok(())
+    }
 
-        prev_expr = Some(expr);
+    fn write_node(
+        &self,
+        w: &mut dyn std::io::Write,
+        id: usize,
+        depth: usize,
+    ) -> std::io::Result<()> {
+        let node = &self.nodes[id];
+        let indent = "    ".repeat(depth);
+        match &node.via {
+            Via::Input => writeln!(w, "{}{}", indent, node.start)?,
+            Via::Eqv(k) => writeln!(w, "{}<=>  {}\t\t( {} )", indent, node.start, k)?,
+            Via::InlineAll => writeln!(w, "{}{}\t\t( inline all )", indent, node.start)?,
+            Via::Tactic(script) =>
+                writeln!(w, "{}{}\t\t( tactic {} )", indent, node.start, script)?,
+        }
+        for (expr, k) in &node.reductions {
+            writeln!(w, "{}{}\t\t\t( {} )", indent, expr, k)?;
+        }
+        for &child in &node.children {
+            self.write_node(w, child, depth + 1)?;
+        }
+        Ok(())
     }
 }
 
@@ -119,3 +721,7 @@ fn print_help_asym() {print!("{}", include_str!("../assets/help-asym.txt"))}
 fn print_help_dom() {print!("{}", include_str!("../assets/help-dom.txt"))}
 fn print_help_triv() {print!("{}", include_str!("../assets/help-triv.txt"))}
 fn print_help_ex() {print!("{}", include_str!("../assets/help-ex.txt"))}
+fn print_help_proof() {print!("{}", include_str!("../assets/help-proof.txt"))}
+fn print_help_load() {print!("{}", include_str!("../assets/help-load.txt"))}
+fn print_help_strategy() {print!("{}", include_str!("../assets/help-strategy.txt"))}
+fn print_help_tactic() {print!("{}", include_str!("../assets/help-tactic.txt"))}
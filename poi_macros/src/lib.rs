@@ -0,0 +1,239 @@
+#![deny(missing_docs)]
+
+//! # Poi Macros
+//!
+//! Compile-time macros for [Poi](https://github.com/advancedresearch/poi).
+//!
+//! `poi!{...}` parses an expression and `rule!{...}` parses knowledge,
+//! generating the same constructor calls that one would write by hand:
+//!
+//! ```
+//! use poi::prelude::*;
+//! use poi_macros::{poi, rule};
+//!
+//! let a = poi!{concat{(: vec)}(x){(: vec)}(y)};
+//! assert_eq!(a, app(constr(app(constr(Concat, app(Rty, VecType)), "x"), app(Rty, VecType)), "y"));
+//!
+//! let k = rule!{and[not] => or};
+//! assert_eq!(k, Red(path(And, Not), Or.into()));
+//! ```
+//!
+//! Since Rust tokens can not contain `\`, `·`, `⨯` or `→`,
+//! write numbers and booleans without `\` and use the alternatives `.`, `x` and `->`,
+//! e.g. `poi!{(not . not)(true)}` or `poi!{f[g x h -> id]}`.
+//! The full syntax is supported by passing a string literal, e.g. `poi!(r"add(\x)(\y)")`.
+//!
+//! Syntax errors are reported as compile errors at the token where parsing failed:
+//!
+//! ```compile_fail
+//! let k = poi_macros::rule!{and[not] => };
+//! ```
+
+extern crate proc_macro;
+
+use proc_macro2::{Delimiter, Literal, Spacing, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, quote_spanned};
+use poi::{Expr, Knowledge, Symbol, Value};
+
+/// Parses an expression at compile time.
+#[proc_macro]
+pub fn poi(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expand(input.into(), |data| poi::parse_str(data).map(|a| expr(&a))).into()
+}
+
+/// Parses a definition, reduction or equivalence at compile time.
+#[proc_macro]
+pub fn rule(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expand(input.into(), |data| match poi::parse_knowledge_str(data)?.as_slice() {
+        [k] => Ok(knowledge(k)),
+        _ => Err("Expected one rule".into()),
+    }).into()
+}
+
+/// Stores source text with the spans of the tokens it was written from.
+struct Source {
+    text: String,
+    /// The character offset where each token starts.
+    spans: Vec<(usize, Span)>,
+}
+
+impl Source {
+    fn new(input: TokenStream) -> Source {
+        if let Ok(lit) = syn::parse2::<syn::LitStr>(input.clone()) {
+            return Source {text: lit.value(), spans: vec![(0, lit.span())]};
+        }
+        let mut src = Source {text: String::new(), spans: vec![]};
+        src.write_tokens(input);
+        src
+    }
+
+    fn push(&mut self, text: &str, span: Span, space: bool) {
+        if space && !self.text.is_empty() {self.text.push(' ')};
+        self.spans.push((self.text.chars().count(), span));
+        self.text.push_str(text);
+    }
+
+    fn write_tokens(&mut self, input: TokenStream) {
+        let mut space = false;
+        for tt in input {
+            match tt {
+                TokenTree::Group(g) => {
+                    let (open, close) = match g.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::None => ("", ""),
+                    };
+                    self.push(open, g.span_open(), space);
+                    self.write_tokens(g.stream());
+                    self.push(close, g.span_close(), false);
+                    space = true;
+                }
+                TokenTree::Punct(p) => {
+                    self.push(&p.as_char().to_string(), p.span(), space);
+                    // Keep `<=>`, `->`, negative numbers, metavariables
+                    // and higher-order variables together.
+                    space = p.spacing() == Spacing::Alone &&
+                        !matches!(p.as_char(), '-' | '?' | '$');
+                }
+                TokenTree::Ident(x) => {
+                    self.push(&x.to_string(), x.span(), space);
+                    space = true;
+                }
+                TokenTree::Literal(x) => {
+                    self.push(&x.to_string(), x.span(), space);
+                    space = true;
+                }
+            }
+        }
+    }
+
+    /// Finds the span of the token at an error reported as `<line>,<column>: ...`.
+    fn error_span(&self, err: &str) -> Span {
+        let pos = err.lines().rev().find_map(|line| {
+            let (pos, _) = line.split_once(':')?;
+            let (line, col) = pos.split_once(',')?;
+            Some((line.parse::<usize>().ok()?, col.parse::<usize>().ok()?))
+        });
+        let offset = match pos {
+            Some((line, col)) => self.text.split('\n').take(line - 1)
+                .map(|s| s.chars().count() + 1).sum::<usize>() + col - 1,
+            None => 0,
+        };
+        self.spans.iter().rev().find(|(start, _)| *start <= offset)
+            .or_else(|| self.spans.first())
+            .map(|(_, span)| *span)
+            .unwrap_or_else(Span::call_site)
+    }
+}
+
+fn expand<F>(input: TokenStream, f: F) -> TokenStream
+    where F: FnOnce(&str) -> Result<TokenStream, String>
+{
+    let src = Source::new(input);
+    match f(&src.text) {
+        Ok(res) => res,
+        Err(err) => {
+            let span = src.error_span(&err);
+            let msg = err.lines().next().unwrap_or("").to_string();
+            quote_spanned!(span=> compile_error!(#msg))
+        }
+    }
+}
+
+fn expr(a: &Expr) -> TokenStream {
+    match a {
+        Expr::Sym(s) => {
+            let s = symbol(s);
+            quote!(::poi::Expr::Sym(#s))
+        }
+        Expr::Ret(v) => {
+            let v = value(v);
+            quote!(::poi::Expr::Ret(#v))
+        }
+        Expr::Op(op, a, b) => {
+            let op = format_ident!("{}", format!("{:?}", op));
+            let (a, b) = (expr(a), expr(b));
+            quote!(::poi::Expr::Op(::poi::Op::#op,
+                ::std::boxed::Box::new(#a), ::std::boxed::Box::new(#b)))
+        }
+        Expr::Tup(items) => {
+            let items = items.iter().map(expr);
+            quote!(::poi::Expr::Tup(::std::vec![#(#items),*]))
+        }
+        Expr::List(items) => {
+            let items = items.iter().map(expr);
+            quote!(::poi::Expr::List(::std::vec![#(#items),*]))
+        }
+    }
+}
+
+fn symbol(s: &Symbol) -> TokenStream {
+    let named = |variant: &str, name: &str| {
+        let variant = format_ident!("{}", variant);
+        let name = string(name);
+        quote!(::poi::Symbol::#variant(#name))
+    };
+    match s {
+        Symbol::Var(x) => named("Var", x),
+        Symbol::ListVar(x) => named("ListVar", x),
+        Symbol::Singleton(x) => named("Singleton", x),
+        Symbol::RetVar(x) => named("RetVar", x),
+        Symbol::NoConstrVar(x) => named("NoConstrVar", x),
+        Symbol::MetaVar(x) => named("MetaVar", x),
+        Symbol::HoVar(f, args) => {
+            let args = args.iter().map(|x| string(x));
+            let f = string(f);
+            quote!(::poi::Symbol::HoVar(#f, ::std::vec![#(#args),*]))
+        }
+        Symbol::Hole(i) => quote!(::poi::Symbol::Hole(#i)),
+        Symbol::UnopRetVar(x, f) => {
+            let (x, f) = (string(x), symbol(f));
+            quote!(::poi::Symbol::UnopRetVar(#x, ::std::boxed::Box::new(#f)))
+        }
+        Symbol::BinopRetVar(x, y, f) => {
+            let (x, y, f) = (string(x), string(y), symbol(f));
+            quote!(::poi::Symbol::BinopRetVar(#x, #y, ::std::boxed::Box::new(#f)))
+        }
+        // The other symbols have no data.
+        _ => {
+            let s = format_ident!("{}", format!("{:?}", s));
+            quote!(::poi::Symbol::#s)
+        }
+    }
+}
+
+fn string(x: &str) -> TokenStream {
+    quote!(::std::sync::Arc::new(::std::string::String::from(#x)))
+}
+
+fn value(v: &Value) -> TokenStream {
+    match v {
+        Value::Bool(x) => quote!(::poi::Value::Bool(#x)),
+        Value::F64(x) => {
+            let x = Literal::f64_suffixed(*x);
+            quote!(::poi::Value::F64(#x))
+        }
+    }
+}
+
+fn knowledge(k: &Knowledge) -> TokenStream {
+    match k {
+        Knowledge::Def(a, b) => {
+            let (a, b) = (symbol(a), expr(b));
+            quote!(::poi::Knowledge::Def(#a, #b))
+        }
+        Knowledge::Red(a, b) => {
+            let (a, b) = (expr(a), expr(b));
+            quote!(::poi::Knowledge::Red(#a, #b))
+        }
+        Knowledge::Eqv(a, b) => {
+            let (a, b) = (expr(a), expr(b));
+            quote!(::poi::Knowledge::Eqv(#a, #b))
+        }
+        Knowledge::CondRed(a, b, c) => {
+            let (a, b, c) = (expr(a), expr(b), expr(c));
+            quote!(::poi::Knowledge::CondRed(#a, #b, #c))
+        }
+    }
+}
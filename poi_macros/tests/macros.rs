@@ -0,0 +1,26 @@
+use poi::prelude::*;
+use poi_macros::{poi, rule};
+
+#[test]
+fn macros() {
+    assert_eq!(poi!{concat{(: vec)}(x){(: vec)}(y)},
+               app(constr(app(constr(Concat, app(Rty, VecType)), "x"), app(Rty, VecType)), "y"));
+    assert_eq!(poi!{(not . not)(true)}, app(comp(Not, Not), true));
+    assert_eq!(poi!{f[g x h -> id]}, parse_str("f[g ⨯ h → id]").unwrap());
+    assert_eq!(poi!{[add(x, -1), 2.5]},
+               List(vec![app(Add, Tup(vec!["x".into(), (-1.0).into()])), 2.5.into()]));
+    assert_eq!(poi!(r"add(\x)(\y)"), parse_str(r"add(\x)(\y)").unwrap());
+    assert_eq!(poi!{?f[not]}, path(MetaVar(std::sync::Arc::new("f".into())), Not));
+
+    assert_eq!(rule!{and[not] => or}, Red(path(And, Not), Or.into()));
+    assert_eq!(rule!{f . g <=> g . f}, Eqv(comp("f", "g"), comp("g", "f")));
+    assert_eq!(rule!{div(x)(x) => 1 when not(eq(x, 0))},
+               parse_knowledge_str(r"div(x)(x) => \1 when not(eq(x, \0))").unwrap()[0]);
+    assert_eq!(rule!{nand := not . and}, Def(Nand, comp(Not, And)));
+
+    let ho = rule!(r"(x, $F(x)) => $F(x)");
+    assert_eq!(ho, parse_knowledge_str(r"(x, $F(x)) => $F(x)").unwrap()[0]);
+    assert_eq!(rule!{(x, $F(x)) => $F(x)}, ho);
+    assert_eq!(poi!(r"$F(x, y)"), parse_str("$F(x, y)").unwrap());
+    assert_eq!(poi!{$F(x, y)}, parse_str("$F(x, y)").unwrap());
+}
@@ -0,0 +1,151 @@
+use std::cmp::Ordering;
+
+use super::*;
+
+/// The maximum number of operands for matching modulo associativity and commutativity.
+const AC_MAX_OPERANDS: usize = 12;
+
+/// Returns the symbols that are declared both `commutative` and `associative`.
+pub fn ac_symbols(knowledge: &[Knowledge]) -> Vec<Symbol> {
+    let mut comm: Vec<&Symbol> = vec![];
+    let mut assoc: Vec<&Symbol> = vec![];
+    for k in knowledge {
+        let (left, right) = match k {
+            Eqv(a, b) => match (binop(a), binop(b)) {
+                (Some(a), Some(b)) if a.0 == b.0 => (a, b),
+                _ => continue,
+            },
+            _ => continue,
+        };
+        let s = left.0;
+        // `s(a)(b) <=> s(b)(a)`
+        if is_var(left.1) && is_var(left.2) && left.1 != left.2 &&
+           left.1 == right.2 && left.2 == right.1 {
+            comm.push(s);
+        }
+        // `s(a)(s(b)(c)) <=> s(s(a)(b))(c)`
+        if let (Some((s2, b, c)), Some((s3, a2, b2))) = (binop(left.2), binop(right.1)) {
+            if s == s2 && s == s3 && is_var(left.1) && is_var(b) && is_var(c) &&
+               left.1 == a2 && b == b2 && c == right.2 {
+                assoc.push(s);
+            }
+        }
+    }
+    comm.into_iter().filter(|s| assoc.contains(s)).cloned().collect()
+}
+
+/// Returns the function and the arguments of a binary application,
+/// either `f(a)(b)` or `f(a, b)`.
+fn binop(expr: &Expr) -> Option<(&Symbol, &Expr, &Expr)> {
+    match expr {
+        Op(Apply, f, b) => match &**f {
+            Op(Apply, f, a) => if let Sym(f) = &**f {Some((f, a, b))} else {None},
+            Sym(f) => match &**b {
+                Tup(items) if items.len() == 2 => Some((f, &items[0], &items[1])),
+                _ => None,
+            },
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_var(expr: &Expr) -> bool {matches!(expr, Sym(Var(_)))}
+
+/// Flattens nested applications of a symbol into its operands.
+fn operands<'a>(s: &Symbol, expr: &'a Expr, res: &mut Vec<&'a Expr>) {
+    match binop(expr) {
+        Some((f, a, b)) if f == s => {
+            operands(s, a, res);
+            operands(s, b, res);
+        }
+        _ => res.push(expr),
+    }
+}
+
+/// Combines operands into nested applications of a symbol, in canonical order.
+fn combine(s: &Symbol, mut items: Vec<Expr>) -> Expr {
+    items.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    let mut expr = items.pop().unwrap();
+    while let Some(a) = items.pop() {expr = app2(s.clone(), a, expr)}
+    expr
+}
+
+impl Context {
+    /// Returns `true` if two expressions are equal modulo associativity and commutativity.
+    pub(crate) fn equal(&self, a: &Expr, b: &Expr) -> bool {
+        a == b || !self.ac.is_empty() && self.normalize(a) == self.normalize(b)
+    }
+
+    /// Flattens and sorts applications of associative and commutative symbols.
+    fn normalize(&self, expr: &Expr) -> Expr {
+        if let Some((s, _, _)) = binop(expr).filter(|(s, _, _)| self.ac.contains(s)) {
+            let mut items = vec![];
+            operands(s, expr, &mut items);
+            return combine(s, items.into_iter().map(|n| self.normalize(n)).collect());
+        }
+        match expr {
+            Sym(_) | Ret(_) => expr.clone(),
+            Op(op, a, b) => Op(*op, Box::new(self.normalize(a)), Box::new(self.normalize(b))),
+            Tup(items) => Tup(items.iter().map(|n| self.normalize(n)).collect()),
+            List(items) => List(items.iter().map(|n| self.normalize(n)).collect()),
+        }
+    }
+
+    /// Matches modulo associativity and commutativity, when matching without it failed.
+    ///
+    /// The context is restored to `n` variables before matching.
+    pub(crate) fn try_bind_ac<'a>(
+        &mut self,
+        name: &'a Expr,
+        value: &'a Expr,
+        n: usize,
+        fail: Fail<'a>
+    ) -> Result<(), Fail<'a>> {
+        let s = match binop(name) {
+            Some((s, _, _)) if self.ac.contains(s) => s,
+            _ => return Err(fail),
+        };
+        let mut values = vec![];
+        operands(s, value, &mut values);
+        if values.len() < 2 || values.len() > AC_MAX_OPERANDS {return Err(fail)};
+
+        let fail = fail.into_owned(self);
+        self.vars.truncate(n);
+        let mut patterns = vec![];
+        operands(s, name, &mut patterns);
+        if self.bind_operands(s, &patterns, &values) {Ok(())} else {
+            self.vars.truncate(n);
+            Err(fail)
+        }
+    }
+
+    /// Binds each pattern to one or more operands, using every operand once.
+    ///
+    /// Only variables can be bound to more than one operand.
+    /// There are no extension variables, so operands that are left over make matching fail.
+    fn bind_operands(&mut self, s: &Symbol, patterns: &[&Expr], values: &[&Expr]) -> bool {
+        let (p, rest) = match patterns.split_first() {
+            Some(x) => x,
+            None => return values.is_empty(),
+        };
+        if values.len() < patterns.len() {return false};
+        let max = if let Sym(Var(_)) | Sym(NoConstrVar(_)) = p {values.len() - rest.len()} else {1};
+        let n = self.vars.len();
+        for size in 1..=max {
+            for mask in 0u32..1 << values.len() {
+                if mask.count_ones() as usize != size {continue};
+                let chosen = (0..values.len()).filter(|i| mask & (1 << i) != 0);
+                let remaining: Vec<&Expr> = (0..values.len())
+                    .filter(|i| mask & (1 << i) == 0).map(|i| values[i]).collect();
+                if rest.is_empty() && !remaining.is_empty() {continue};
+                let value = combine(s, chosen.map(|i| values[i].clone()).collect());
+                if self.try_bind(p, &value).is_ok() && self.bind_operands(s, rest, &remaining) {
+                    return true;
+                }
+                self.vars.truncate(n);
+            }
+        }
+        false
+    }
+}
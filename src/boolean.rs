@@ -0,0 +1,427 @@
+use super::*;
+
+/// The built-in boolean functions of one argument.
+pub const BOOLEAN_UNOPS: [Symbol; 4] = [False1, Not, Idb, True1];
+
+/// The built-in boolean functions of two arguments.
+pub const BOOLEAN_BINOPS: [Symbol; 12] = [
+    And, Or, Eqb, Xor, Nand, Nor, Exc, Imply, Fstb, Sndb, False2, True2
+];
+
+/// The largest number of arguments of boolean functions that are evaluated for every input.
+///
+/// Truth tables grow exponentially with the number of arguments,
+/// so paths, normal forms and decisions give `Error::TooManyArguments` for larger functions.
+pub const BOOLEAN_MAX_ARITY: usize = 3;
+
+/// Computes the truth table of a boolean function by evaluating it for every input.
+///
+/// Row `i` is the output for the inputs given by the bits of `i`,
+/// where the first argument is the highest bit and `1` is `true`.
+/// This works for compositions of built-in symbols and definitions that reduce to `if` tables.
+pub fn truth_table(f: &Expr, arity: usize, knowledge: &[Knowledge]) -> Result<Vec<bool>, Error> {
+    acyclic(f, knowledge)?;
+    (0..1 << arity).map(|i| match apply(f, &bits(i, arity), knowledge)?.as_slice() {
+        &[x] => Ok(x),
+        _ => Err(Error::InvalidComputation),
+    }).collect()
+}
+
+/// Computes the existential path `∃(f)` of a function with boolean inputs and outputs.
+///
+/// The result is the function that tells which outputs `f` can return,
+/// e.g. `∃(and) = true1` and `∃(not · false1) = idb`.
+/// For functions that return tuples, the result takes one argument per item.
+pub fn existential_path(f: &Expr, knowledge: &[Knowledge]) -> Result<Expr, Error> {
+    let outputs = domain(f, knowledge)?;
+    let width = width(&outputs)?;
+    let table: Vec<bool> = (0..1 << width).map(|i| {
+        outputs.iter().flatten().any(|y| y == &bits(i, width))
+    }).collect();
+    Ok(boolean_function(&table, width, knowledge))
+}
+
+/// Computes the trivial path `∀(f)` of a function with boolean inputs.
+///
+/// The result is `\true` when `f` is defined for every input,
+/// or else the function that tells which inputs satisfy the domain constraints of `f`.
+pub fn trivial_path(f: &Expr, knowledge: &[Knowledge]) -> Result<Expr, Error> {
+    let outputs = domain(f, knowledge)?;
+    if outputs.iter().all(|y| y.is_some()) {return Ok(true.into())};
+    let table: Vec<bool> = outputs.iter().map(|y| y.is_some()).collect();
+    Ok(boolean_function(&table, outputs.len().trailing_zeros() as usize, knowledge))
+}
+
+/// Computes the normal form of a closed boolean expression.
+///
+/// The normal form is the `if` tree of the truth table, see `if_tree`,
+/// so expressions have the same normal form exactly when they compute the same function.
+/// For example, `and[not]` and `or` both have the normal form `if(\true, if(\true, \false))`.
+/// Functions that return tuples have a tuple of `if` trees,
+/// and partial functions are constrained by the `if` tree of their domain.
+pub fn boolean_normal_form(a: &Expr, knowledge: &[Knowledge]) -> Result<Expr, Error> {
+    let outputs = domain(a, knowledge)?;
+    let trees: Vec<Expr> = (0..width(&outputs)?).map(|k| {
+        if_tree(&outputs.iter().map(|y| y.as_ref().map(|y| y[k]).unwrap_or(false))
+            .collect::<Vec<bool>>())
+    }).collect();
+    let res = if trees.len() == 1 {trees[0].clone()} else {Tup(trees)};
+    if outputs.iter().all(|y| y.is_some()) {return Ok(res)};
+    Ok(constr(res, if_tree(&outputs.iter().map(|y| y.is_some()).collect::<Vec<bool>>())))
+}
+
+/// Decides whether two closed boolean expressions compute the same function.
+///
+/// This compares normal forms, see `boolean_normal_form`,
+/// which is complete for expressions of the built-in boolean symbols, `if`,
+/// compositions, tuples, domain constraints and paths.
+/// Returns `Error::ArityMismatch` when the functions take different numbers of arguments,
+/// e.g. `idb` and `fstb`, which have the same normal form.
+pub fn boolean_eqv(a: &Expr, b: &Expr, knowledge: &[Knowledge]) -> Result<bool, Error> {
+    if boolean_arity(a, knowledge)? != boolean_arity(b, knowledge)? {
+        return Err(Error::ArityMismatch)
+    };
+    Ok(boolean_normal_form(a, knowledge)? == boolean_normal_form(b, knowledge)?)
+}
+
+/// Computes the normal path `f[g0 ⨯ g1 → g2]` of a boolean function.
+///
+/// Returns a function `h` such that `h(g0(a), g1(b)) = g2(f(a, b))` for all `a` and `b`,
+/// as a built-in symbol when there is one with the same truth table, or else as an `if` table.
+/// The arity of `f` is the number of arguments `args`, so `f[g0 → g1]` has one argument.
+///
+/// Returns `Error::NoPath` when no function satisfies the equation,
+/// or when several functions do, because some inputs of `h` are never reached.
+pub fn boolean_path(
+    f: &Expr,
+    args: &[Expr],
+    ret: &Expr,
+    knowledge: &[Knowledge]
+) -> Result<Expr, Error> {
+    let arity = args.len();
+    let f = truth_table(f, arity, knowledge)?;
+    let args = args.iter().map(|g| truth_table(g, 1, knowledge))
+        .collect::<Result<Vec<_>, _>>()?;
+    let ret = truth_table(ret, 1, knowledge)?;
+    let mut h: Vec<Option<bool>> = vec![None; f.len()];
+    for (i, &y) in f.iter().enumerate() {
+        let j = (0..arity).fold(0, |j, k| {
+            let x = (i >> (arity - 1 - k)) & 1;
+            (j << 1) | args[k][x] as usize
+        });
+        let y = ret[y as usize];
+        match h[j] {
+            Some(z) if z != y => return Err(Error::NoPath),
+            _ => h[j] = Some(y),
+        }
+    }
+    let h = h.into_iter().collect::<Option<Vec<bool>>>().ok_or(Error::NoPath)?;
+    Ok(boolean_function(&h, arity, knowledge))
+}
+
+/// Derives the reductions `f[g0 ⨯ g1 → g2] => h` for boolean functions of some arity.
+///
+/// Every function in `fs` is combined with every choice of `gs` for the arguments and the result.
+/// The short form `f[g]` is used when the same function is chosen for all of them.
+/// Combinations without a path are skipped.
+pub fn boolean_path_rules(
+    fs: &[Expr],
+    arity: usize,
+    gs: &[Expr],
+    knowledge: &[Knowledge]
+) -> Vec<Knowledge> {
+    let mut res = vec![];
+    for f in fs {
+        for i in 0..gs.len().pow(arity as u32 + 1) {
+            let choice: Vec<Expr> = (0..=arity).map(|k| {
+                gs[i / gs.len().pow((arity - k) as u32) % gs.len()].clone()
+            }).collect();
+            let (args, ret) = choice.split_at(arity);
+            if let Ok(h) = boolean_path(f, args, &ret[0], knowledge) {
+                let g = if choice.iter().all(|g| g == &ret[0]) {ret[0].clone()}
+                        else {Tup(choice.clone())};
+                res.push(Red(path(f.clone(), g), h));
+            }
+        }
+    }
+    res
+}
+
+/// Generates the definition of a boolean function from its truth table, see `if_tree`.
+pub fn boolean_def(f: Symbol, table: &[bool]) -> Knowledge {Def(f, if_tree(table))}
+
+/// Builds nested `if` expressions that branch on the first argument, then the second, and so on.
+///
+/// The table uses the rows of `truth_table` and its length must be a power of two.
+/// Constant parts of the table become values, e.g. `[false, false, false, true]`
+/// gives `if(if(\true, \false), \false)`, which is the definition of `and`.
+pub fn if_tree(table: &[bool]) -> Expr {
+    if table.iter().all(|&x| x == table[0]) {return table[0].into()};
+    let (lower, upper) = table.split_at(table.len() / 2);
+    _if(if_tree(upper), if_tree(lower))
+}
+
+/// Returns the built-in boolean symbol that computes the same function as an expression.
+///
+/// This recognizes `if` trees and other closed boolean expressions,
+/// e.g. `if(if(\false, \true), \true)` and `not · and` are both `nand`.
+/// The arity is the number of arguments of the expression, see `boolean_arity`.
+pub fn boolean_symbol(f: &Expr, knowledge: &[Knowledge]) -> Option<Symbol> {
+    let outputs = domain(f, knowledge).ok()?;
+    let table = outputs.iter().map(|y| match y.as_deref() {
+        Some(&[x]) => Some(x),
+        _ => None,
+    }).collect::<Option<Vec<bool>>>()?;
+    named(&table, table.len().trailing_zeros() as usize, knowledge)
+}
+
+impl Expr {
+    /// Replaces the first `if` tree with the built-in boolean symbol it computes.
+    ///
+    /// Sub-expressions are visited with parents before children.
+    /// This is used to fold the output of inlining back into symbols.
+    /// Returns the new expression and the definition `f := a` that was used backwards,
+    /// or `None` when there is no such `if` tree.
+    pub fn fold_boolean(&self, knowledge: &[Knowledge]) -> Option<(Expr, Knowledge)> {
+        self.positions().into_iter().find_map(|pos| {
+            let sub = self.get(&pos)?;
+            if !has_if(sub) {return None};
+            let f = boolean_symbol(sub, knowledge)?;
+            Some((self.replace(&pos, f.clone().into())?, Def(f, sub.clone())))
+        })
+    }
+}
+
+/// Returns `true` if an expression contains `if`.
+fn has_if(a: &Expr) -> bool {
+    match a {
+        Sym(If) => true,
+        _ => a.children().into_iter().any(has_if),
+    }
+}
+
+/// Returns the built-in symbol with a truth table, or an `if` tree when there is none.
+fn boolean_function(table: &[bool], arity: usize, knowledge: &[Knowledge]) -> Expr {
+    match named(table, arity, knowledge) {
+        Some(f) => f.into(),
+        None => if_tree(table),
+    }
+}
+
+/// Returns the built-in symbol with a truth table.
+fn named(table: &[bool], arity: usize, knowledge: &[Knowledge]) -> Option<Symbol> {
+    let symbols: &[Symbol] = match arity {
+        1 => &BOOLEAN_UNOPS,
+        2 => &BOOLEAN_BINOPS,
+        _ => &[],
+    };
+    symbols.iter().find(|f| {
+        truth_table(&(*f).clone().into(), arity, knowledge).as_deref() == Ok(table)
+    }).cloned()
+}
+
+/// Returns the inputs for row `i` of a truth table.
+fn bits(i: usize, arity: usize) -> Vec<bool> {
+    (0..arity).map(|k| (i >> (arity - 1 - k)) & 1 == 1).collect()
+}
+
+/// Returns the number of outputs, which must be the same for every input.
+fn width(outputs: &[Option<Vec<bool>>]) -> Result<usize, Error> {
+    let width = match outputs.iter().flatten().next() {
+        Some(y) => y.len(),
+        None => return Err(Error::InvalidComputation),
+    };
+    if outputs.iter().flatten().any(|y| y.len() != width) {return Err(Error::InvalidComputation)};
+    Ok(width)
+}
+
+/// Returns the number of arguments of a closed boolean expression.
+///
+/// The arity follows from the built-in symbols and definitions in the expression,
+/// e.g. `not · and` has two arguments and `if(idb, \false)` has two arguments.
+/// Constants take no arguments, and branches of `if` that are constants
+/// take as many arguments as the other branch.
+/// Returns `Error::ArityMismatch` when the parts of the expression disagree,
+/// e.g. `(idb, fst)`.
+pub fn boolean_arity(f: &Expr, knowledge: &[Knowledge]) -> Result<usize, Error> {
+    acyclic(f, knowledge)?;
+    Ok(arity(f, knowledge)?.unwrap_or(0))
+}
+
+/// Returns the number of arguments of an expression, or `None` for constants.
+fn arity(f: &Expr, knowledge: &[Knowledge]) -> Result<Option<usize>, Error> {
+    let same = |a: Option<usize>, b: Option<usize>| match (a, b) {
+        (Some(a), Some(b)) if a != b => Err(Error::ArityMismatch),
+        _ => Ok(a.or(b)),
+    };
+    match f {
+        Ret(Bool(_)) => Ok(None),
+        Sym(a) => match symbol_arity(a) {
+            Some(n) => Ok(Some(n)),
+            None => match knowledge.iter().find_map(|k| match k {
+                Def(b, c) if a == b => Some(c),
+                _ => None,
+            }) {
+                Some(c) => arity(c, knowledge),
+                None => Err(Error::NoDefinition),
+            },
+        },
+        Op(Apply, g, b) => match (&**g, &**b) {
+            (Op(Apply, g, a), b) if **g == Sym(If) => {
+                Ok(Some(1 + same(arity(a, knowledge)?, arity(b, knowledge)?)?.unwrap_or(0)))
+            }
+            (Sym(If), Tup(items)) if items.len() == 2 => {
+                let n = same(arity(&items[0], knowledge)?, arity(&items[1], knowledge)?)?;
+                Ok(Some(1 + n.unwrap_or(0)))
+            }
+            (g, b) => {
+                let n = if let Tup(items) = b {items.len()} else {1};
+                match arity(g, knowledge)? {
+                    Some(m) if m < n => Err(Error::InvalidComputation),
+                    m => Ok(m.map(|m| m - n)),
+                }
+            }
+        },
+        Op(Compose, _, h) => arity(h, knowledge),
+        Op(Path, f, g) => match &**g {
+            Tup(gs) if gs.len() >= 2 => same(arity(f, knowledge)?, Some(gs.len() - 1)),
+            _ => arity(f, knowledge),
+        },
+        Op(Constrain, g, h) => same(arity(g, knowledge)?, arity(h, knowledge)?),
+        Tup(items) => {
+            let mut n = None;
+            for a in items {n = same(n, arity(a, knowledge)?)?}
+            Ok(n)
+        }
+        Op(..) | List(_) | Ret(_) => Err(Error::InvalidComputation),
+    }
+}
+
+/// Returns `Error::CyclicDefinition` when a definition used by an expression refers to itself,
+/// e.g. `foo := not · foo`.
+///
+/// Evaluation and `arity` follow definitions, so this is checked before using them.
+fn acyclic(f: &Expr, knowledge: &[Knowledge]) -> Result<(), Error> {
+    fn visit<'a>(
+        f: &'a Expr,
+        knowledge: &'a [Knowledge],
+        stack: &mut Vec<&'a Symbol>,
+        done: &mut Vec<&'a Symbol>
+    ) -> Result<(), Error> {
+        match f {
+            Sym(a) if stack.contains(&a) => Err(Error::CyclicDefinition),
+            Sym(a) if done.contains(&a) => Ok(()),
+            Sym(a) => {
+                if let Some(c) = knowledge.iter().find_map(|k| match k {
+                    Def(b, c) if a == b => Some(c),
+                    _ => None,
+                }) {
+                    stack.push(a);
+                    visit(c, knowledge, stack, done)?;
+                    stack.pop();
+                }
+                done.push(a);
+                Ok(())
+            }
+            _ => {
+                for a in f.children() {visit(a, knowledge, stack, done)?}
+                Ok(())
+            }
+        }
+    }
+    visit(f, knowledge, &mut vec![], &mut vec![])
+}
+
+/// Returns the number of arguments of a built-in boolean symbol.
+fn symbol_arity(f: &Symbol) -> Option<usize> {
+    if *f == Id || BOOLEAN_UNOPS.contains(f) {Some(1)}
+    else if matches!(f, Fst | Snd | Eq) || BOOLEAN_BINOPS.contains(f) {Some(2)}
+    else {None}
+}
+
+/// Evaluates a function for every input.
+///
+/// Inputs that do not satisfy the domain constraints of the function have no output.
+fn domain(f: &Expr, knowledge: &[Knowledge]) -> Result<Vec<Option<Vec<bool>>>, Error> {
+    let arity = boolean_arity(f, knowledge)?;
+    if arity > BOOLEAN_MAX_ARITY {return Err(Error::TooManyArguments)};
+    (0..1 << arity).map(|i| match apply(f, &bits(i, arity), knowledge) {
+        Ok(y) => Ok(Some(y)),
+        Err(Error::ConditionDoesNotHold) => Ok(None),
+        Err(err) => Err(err),
+    }).collect()
+}
+
+/// Applies a function to boolean inputs and returns its outputs.
+///
+/// Constants ignore their inputs, like in `\x(_) => x`,
+/// while built-in symbols fail unless they get their number of arguments.
+/// The identity `id` also passes on the outputs of functions that return tuples.
+/// Returns `Error::ConditionDoesNotHold` when the inputs do not satisfy a domain constraint.
+fn apply(f: &Expr, args: &[bool], knowledge: &[Knowledge]) -> Result<Vec<bool>, Error> {
+    match f {
+        Ret(Bool(x)) => Ok(vec![*x]),
+        Sym(Id) if !args.is_empty() => Ok(args.into()),
+        Sym(a) if symbol_arity(a).is_some_and(|n| n != args.len()) => {
+            Err(Error::InvalidComputation)
+        }
+        Sym(Idb) => Ok(args.into()),
+        Sym(Fst) | Sym(Fstb) => Ok(vec![args[0]]),
+        Sym(Snd) | Sym(Sndb) => Ok(vec![args[1]]),
+        Sym(Eq) => Ok(vec![args[0] == args[1]]),
+        Sym(a) => match knowledge.iter().find_map(|k| match k {
+            Def(b, c) if a == b => Some(c),
+            _ => None,
+        }) {
+            Some(c) => apply(c, args, knowledge),
+            None => Err(Error::NoDefinition),
+        },
+        Op(Apply, g, b) => match (&**g, &**b) {
+            (Op(Apply, g, a), b) if **g == Sym(If) => apply_if(a, b, args, knowledge),
+            (Sym(If), Tup(items)) if items.len() == 2 => {
+                apply_if(&items[0], &items[1], args, knowledge)
+            }
+            _ => {
+                let mut xs = match &**b {
+                    Ret(Bool(x)) => vec![*x],
+                    Tup(items) => items.iter().map(|a| match a {
+                        Ret(Bool(x)) => Ok(*x),
+                        _ => Err(Error::InvalidComputation),
+                    }).collect::<Result<_, _>>()?,
+                    _ => return Err(Error::InvalidComputation),
+                };
+                xs.extend_from_slice(args);
+                apply(g, &xs, knowledge)
+            }
+        },
+        Op(Compose, g, h) => apply(g, &apply(h, args, knowledge)?, knowledge),
+        Op(Path, f, g) => {
+            let (gs, ret) = match &**g {
+                Tup(gs) if gs.len() >= 2 => (gs[..gs.len() - 1].to_vec(), gs[gs.len() - 1].clone()),
+                _ => (vec![(**g).clone(); boolean_arity(f, knowledge)?], (**g).clone()),
+            };
+            apply(&boolean_path(f, &gs, &ret, knowledge)?, args, knowledge)
+        }
+        Op(Constrain, g, h) => {
+            if apply(h, args, knowledge)? == [true] {apply(g, args, knowledge)}
+            else {Err(Error::ConditionDoesNotHold)}
+        }
+        Tup(items) => {
+            let mut res = vec![];
+            for a in items {res.extend(apply(a, args, knowledge)?)}
+            Ok(res)
+        }
+        Op(..) | List(_) | Ret(_) => Err(Error::InvalidComputation),
+    }
+}
+
+/// Applies `if(a, b)`, which returns `a` for `true` and `b` for `false`.
+fn apply_if(
+    a: &Expr,
+    b: &Expr,
+    args: &[bool],
+    knowledge: &[Knowledge]
+) -> Result<Vec<bool>, Error> {
+    let (x, args) = args.split_first().ok_or(Error::InvalidComputation)?;
+    apply(if *x {a} else {b}, args, knowledge)
+}
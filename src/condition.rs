@@ -0,0 +1,108 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use super::*;
+
+/// The maximum depth of conditions that are checked while checking another condition.
+///
+/// A condition is evaluated using the knowledge base,
+/// which can use conditional reductions whose conditions are evaluated in turn.
+pub const CONDITION_MAX_DEPTH: usize = 16;
+
+/// A predicate that discharges conditions that do not evaluate to `\true`.
+///
+/// The predicate gets the condition after substitution,
+/// e.g. `not(eq(a, \0))` for `div(x)(x) => \1 when not(eq(x, \0))` on `div(a)(a)`.
+pub type Discharge<'a> = &'a dyn Fn(&Expr) -> bool;
+
+/// Stores what is passed on while reducing, including into nested conditions.
+#[derive(Clone)]
+pub(crate) struct Env<'a> {
+    /// Symbols that are matched modulo associativity and commutativity.
+    pub ac: Vec<Symbol>,
+    /// Discharges conditions that do not evaluate to `\true`.
+    pub discharge: Option<Discharge<'a>>,
+    /// The number of conditions that are checked.
+    pub depth: usize,
+    /// Set when the maximum depth is exceeded, until the outermost condition returns.
+    pub too_deep: Rc<Cell<bool>>,
+}
+
+impl<'a> Env<'a> {
+    /// Creates a new environment for reducing with a knowledge base.
+    pub fn new(knowledge: &[Knowledge]) -> Env<'a> {
+        Env {
+            ac: ac_symbols(knowledge),
+            discharge: None,
+            depth: 0,
+            too_deep: Rc::new(Cell::new(false)),
+        }
+    }
+
+    /// Creates a new environment where conditions can be discharged by a predicate.
+    pub fn with_discharge(knowledge: &[Knowledge], discharge: Discharge<'a>) -> Env<'a> {
+        Env {discharge: Some(discharge), ..Env::new(knowledge)}
+    }
+}
+
+impl Expr {
+    /// Reduces expression one step using a knowledge base,
+    /// where conditions of conditional reductions that do not evaluate to `\true`
+    /// can be discharged by a predicate.
+    pub fn reduce_with_discharge(
+        &self,
+        knowledge: &[Knowledge],
+        discharge: Discharge
+    ) -> Result<(Expr, usize), Error> {
+        self.reduce_env(knowledge, &Env::with_discharge(knowledge, discharge))
+    }
+
+    /// Reduces an expression using a knowledge base, until it can not be reduced further,
+    /// where conditions can be discharged by a predicate, see `reduce_with_discharge`.
+    pub fn reduce_all_with_discharge(&self, knowledge: &[Knowledge], discharge: Discharge) -> Expr {
+        let env = Env::with_discharge(knowledge, discharge);
+        let mut me = self.clone();
+        while let Ok((expr, _)) = me.reduce_env(knowledge, &env) {me = expr}
+        me
+    }
+}
+
+impl Context {
+    /// Checks the condition of a conditional reduction.
+    ///
+    /// Returns the condition after substitution, when it evaluates to `\true`.
+    /// Returns `Error::ConditionTooDeep` when checking the condition needs to check
+    /// more than `CONDITION_MAX_DEPTH` nested conditions.
+    pub fn condition(&self, cond: &Expr, knowledge: &[Knowledge]) -> Result<Expr, Error> {
+        self.condition_env(cond, knowledge, &Env::new(knowledge))
+    }
+
+    /// Checks the condition of a conditional reduction,
+    /// which is also discharged by the predicate of the environment.
+    pub(crate) fn condition_env(
+        &self,
+        cond: &Expr,
+        knowledge: &[Knowledge],
+        env: &Env
+    ) -> Result<Expr, Error> {
+        if env.depth >= CONDITION_MAX_DEPTH || env.too_deep.get() {
+            env.too_deep.set(true);
+            return Err(Error::ConditionTooDeep);
+        }
+
+        let cond = self.substitute_in(cond, knowledge)?;
+        let nested = Env {depth: env.depth + 1, ..env.clone()};
+        let res = match cond.eval_env(knowledge, &nested) {
+            Ok(Ret(Bool(true))) => Ok(()),
+            Ok(_) => Err(Error::ConditionDoesNotHold),
+            Err(err) => Err(err),
+        };
+        let too_deep = if env.depth == 0 {env.too_deep.replace(false)} else {env.too_deep.get()};
+        match res {
+            _ if too_deep => Err(Error::ConditionTooDeep),
+            Ok(()) => Ok(cond),
+            Err(_) if env.discharge.is_some_and(|f| f(&cond)) => Ok(cond),
+            Err(err) => Err(err),
+        }
+    }
+}
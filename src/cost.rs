@@ -0,0 +1,111 @@
+use super::*;
+
+/// Computes the cost of a node from the costs of its children.
+///
+/// The cost should be larger than the cost of any child.
+pub trait Cost {
+    /// Returns the cost of a node.
+    ///
+    /// For operators, the costs of the left and right argument are given.
+    /// The classes of the children are only meaningful in an e-graph.
+    fn cost(&self, node: &ENode, children: &[f64]) -> f64;
+}
+
+impl<F: Fn(&ENode, &[f64]) -> f64> Cost for F {
+    fn cost(&self, node: &ENode, children: &[f64]) -> f64 {self(node, children)}
+}
+
+/// Prefers the expression with fewest nodes.
+#[derive(Clone, Copy, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Size;
+
+impl Cost for Size {
+    fn cost(&self, _: &ENode, children: &[f64]) -> f64 {
+        1.0 + children.iter().sum::<f64>()
+    }
+}
+
+/// Assigns a cost to each symbol and operator.
+///
+/// The cost of an expression is the sum of the costs of its nodes.
+/// For example, to prefer `add(len(a))(len(b))` over `len(concat(a)(b))`,
+/// make `concat` more expensive than `add` and `len`.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CostModel {
+    /// The cost of symbols without a specific cost.
+    pub symbol: f64,
+    /// The cost of values.
+    pub value: f64,
+    /// The cost of tuples and lists.
+    pub items: f64,
+    /// The cost of operators without a specific cost.
+    pub op: f64,
+    /// Specific costs of symbols.
+    pub symbols: Vec<(Symbol, f64)>,
+    /// Specific costs of operators.
+    pub ops: Vec<(Op, f64)>,
+}
+
+impl Default for CostModel {
+    fn default() -> CostModel {CostModel::new()}
+}
+
+impl CostModel {
+    /// Creates a new cost model where every node costs `1`.
+    pub fn new() -> CostModel {
+        CostModel {
+            symbol: 1.0,
+            value: 1.0,
+            items: 1.0,
+            op: 1.0,
+            symbols: vec![],
+            ops: vec![],
+        }
+    }
+
+    /// Sets the cost of a symbol.
+    pub fn symbol(mut self, sym: Symbol, cost: f64) -> CostModel {
+        self.symbols.retain(|(s, _)| s != &sym);
+        self.symbols.push((sym, cost));
+        self
+    }
+
+    /// Sets the cost of an operator.
+    pub fn op(mut self, op: Op, cost: f64) -> CostModel {
+        self.ops.retain(|(o, _)| o != &op);
+        self.ops.push((op, cost));
+        self
+    }
+}
+
+impl Cost for CostModel {
+    fn cost(&self, node: &ENode, children: &[f64]) -> f64 {
+        let c = match node {
+            ENode::Sym(a) => self.symbols.iter().find(|(s, _)| s == a)
+                .map(|(_, c)| *c).unwrap_or(self.symbol),
+            ENode::Ret(_) => self.value,
+            ENode::Op(a, _, _) => self.ops.iter().find(|(o, _)| o == a)
+                .map(|(_, c)| *c).unwrap_or(self.op),
+            ENode::Tup(_) | ENode::List(_) => self.items,
+        };
+        c + children.iter().sum::<f64>()
+    }
+}
+
+impl Expr {
+    /// Computes the cost of the expression.
+    pub fn cost(&self, cost: &dyn Cost) -> f64 {
+        let children: Vec<f64> = self.children().into_iter().map(|n| n.cost(cost)).collect();
+        let ids = 0..;
+        let node = match self {
+            Sym(a) => ENode::Sym(a.clone()),
+            Ret(a) => ENode::Ret(a.clone()),
+            Op(op, _, _) => ENode::Op(*op, 0, 1),
+            Tup(items) => ENode::Tup(ids.take(items.len()).collect()),
+            List(items) => ENode::List(ids.take(items.len()).collect()),
+        };
+        cost.cost(&node, &children)
+    }
+}
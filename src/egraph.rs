@@ -0,0 +1,474 @@
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+use super::*;
+
+/// The index of an equivalence class in an e-graph.
+pub type ClassId = usize;
+
+/// A node in an e-graph, where children are equivalence classes.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ENode {
+    /// A symbol.
+    Sym(Symbol),
+    /// A value.
+    Ret(Value),
+    /// A binary operation.
+    Op(Op, ClassId, ClassId),
+    /// A tuple.
+    Tup(Vec<ClassId>),
+    /// A list.
+    List(Vec<ClassId>),
+}
+
+// Values are compared with `Value::same`, such that NaN nodes are equal to themselves.
+impl PartialEq for ENode {
+    fn eq(&self, other: &ENode) -> bool {
+        match (self, other) {
+            (ENode::Sym(a), ENode::Sym(b)) => a == b,
+            (ENode::Ret(a), ENode::Ret(b)) => a.same(b),
+            (ENode::Op(op0, a0, b0), ENode::Op(op1, a1, b1)) => op0 == op1 && a0 == a1 && b0 == b1,
+            (ENode::Tup(a), ENode::Tup(b)) | (ENode::List(a), ENode::List(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl std::cmp::Eq for ENode {}
+
+impl Hash for ENode {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            ENode::Sym(a) => {0.hash(state); a.hash(state)}
+            ENode::Ret(a) => {1.hash(state); a.hash(state)}
+            ENode::Op(op, a, b) => {2.hash(state); op.hash(state); a.hash(state); b.hash(state)}
+            ENode::Tup(a) => {3.hash(state); a.hash(state)}
+            ENode::List(a) => {4.hash(state); a.hash(state)}
+        }
+    }
+}
+
+impl ENode {
+    /// Returns the children of the node.
+    pub fn children(&self) -> Vec<ClassId> {
+        match self {
+            ENode::Sym(_) | ENode::Ret(_) => vec![],
+            ENode::Op(_, a, b) => vec![*a, *b],
+            ENode::Tup(items) | ENode::List(items) => items.clone(),
+        }
+    }
+
+    fn map_children(&self, f: impl Fn(ClassId) -> ClassId) -> ENode {
+        match self {
+            ENode::Sym(_) | ENode::Ret(_) => self.clone(),
+            ENode::Op(op, a, b) => ENode::Op(*op, f(*a), f(*b)),
+            ENode::Tup(items) => ENode::Tup(items.iter().map(|&i| f(i)).collect()),
+            ENode::List(items) => ENode::List(items.iter().map(|&i| f(i)).collect()),
+        }
+    }
+}
+
+/// Stores many equivalent expressions compactly, by sharing equivalence classes.
+///
+/// Rules are applied in every direction they can be used
+/// until no new equalities are found, which is called "equality saturation".
+/// The best expression can then be extracted using a cost function.
+#[derive(Clone, Debug, Default)]
+pub struct EGraph {
+    /// Union-find of equivalence classes.
+    parents: Vec<ClassId>,
+    /// The nodes of each class, only used for canonical classes.
+    classes: Vec<Vec<ENode>>,
+    /// Looks up the class of a canonical node.
+    memo: HashMap<ENode, ClassId>,
+}
+
+/// The target of a pattern variable.
+///
+/// Patterns like `(x, y..)` bind the tail to items that might not be a class yet.
+#[derive(Clone, PartialEq, Debug)]
+enum Target {
+    Class(ClassId),
+    Tup(Vec<ClassId>),
+    List(Vec<ClassId>),
+}
+
+type Subst = Vec<(Arc<String>, Target)>;
+
+impl EGraph {
+    /// Creates a new empty e-graph.
+    pub fn new() -> EGraph {EGraph::default()}
+
+    /// Returns the number of nodes.
+    pub fn node_count(&self) -> usize {
+        (0..self.classes.len()).filter(|&i| self.find(i) == i).map(|i| self.classes[i].len()).sum()
+    }
+
+    /// Returns the number of equivalence classes.
+    pub fn class_count(&self) -> usize {
+        (0..self.parents.len()).filter(|&i| self.find(i) == i).count()
+    }
+
+    /// Returns the canonical class of a class.
+    pub fn find(&self, mut id: ClassId) -> ClassId {
+        while self.parents[id] != id {id = self.parents[id]}
+        id
+    }
+
+    /// Returns `true` if two classes are equivalent.
+    pub fn equivalent(&self, a: ClassId, b: ClassId) -> bool {self.find(a) == self.find(b)}
+
+    /// Returns the nodes of a class.
+    pub fn nodes(&self, id: ClassId) -> &[ENode] {&self.classes[self.find(id)]}
+
+    /// Adds a node and returns its class.
+    pub fn add(&mut self, node: ENode) -> ClassId {
+        let node = node.map_children(|i| self.find(i));
+        if let Some(&id) = self.memo.get(&node) {return self.find(id)};
+        let id = self.parents.len();
+        self.parents.push(id);
+        self.classes.push(vec![node.clone()]);
+        self.memo.insert(node, id);
+        id
+    }
+
+    /// Adds an expression and returns its class.
+    pub fn add_expr(&mut self, expr: &Expr) -> ClassId {
+        let node = match expr {
+            Sym(a) => ENode::Sym(a.clone()),
+            Ret(a) => ENode::Ret(a.clone()),
+            Op(op, a, b) => {
+                let a = self.add_expr(a);
+                ENode::Op(*op, a, self.add_expr(b))
+            }
+            Tup(items) => ENode::Tup(items.iter().map(|n| self.add_expr(n)).collect()),
+            List(items) => ENode::List(items.iter().map(|n| self.add_expr(n)).collect()),
+        };
+        self.add(node)
+    }
+
+    /// Merges two classes.
+    ///
+    /// Returns `true` if the classes were not equivalent before.
+    /// Call `EGraph::rebuild` afterwards to restore sharing.
+    pub fn union(&mut self, a: ClassId, b: ClassId) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {return false};
+        let (a, b) = (a.min(b), a.max(b));
+        self.parents[b] = a;
+        let nodes = std::mem::take(&mut self.classes[b]);
+        self.classes[a].extend(nodes);
+        true
+    }
+
+    /// Restores sharing after merging classes.
+    ///
+    /// Nodes that became equal by merging their children are merged too.
+    pub fn rebuild(&mut self) {
+        loop {
+            let mut memo: HashMap<ENode, ClassId> = HashMap::new();
+            let mut merged = vec![];
+            for id in 0..self.classes.len() {
+                if self.find(id) != id {continue};
+                let mut nodes: Vec<ENode> = vec![];
+                for node in &self.classes[id] {
+                    let node = node.map_children(|i| self.find(i));
+                    if !nodes.contains(&node) {nodes.push(node)};
+                }
+                for node in &nodes {
+                    match memo.get(node) {
+                        Some(&other) => merged.push((other, id)),
+                        None => {memo.insert(node.clone(), id);}
+                    }
+                }
+                self.classes[id] = nodes;
+            }
+            self.memo = memo;
+            let mut changed = false;
+            for (a, b) in merged {changed |= self.union(a, b)}
+            if !changed {break};
+        }
+    }
+
+    /// Applies all reductions and equivalences until no new equalities are found,
+    /// or the number of added nodes exceeds a limit.
+    ///
+    /// Reductions are only used forward, while equivalences are used both ways.
+    /// Equivalences are not used on functions that are applied to arguments,
+    /// e.g. `(f · g)(a) <=> f(g(a))` is not used on `(f · g)(a)(b)`.
+    /// Conditional reductions are not used, since conditions are not known for classes.
+    /// Returns `true` if the e-graph was saturated.
+    pub fn saturate(&mut self, knowledge: &[Knowledge], node_limit: usize) -> bool {
+        // Stores whether the rule is an equivalence.
+        let mut rules: Vec<(&Expr, &Expr, bool)> = vec![];
+        for k in knowledge {
+            match k {
+                Red(a, b) => rules.push((a, b, false)),
+                Eqv(a, b) => {
+                    rules.push((a, b, true));
+                    rules.push((b, a, true));
+                }
+                Def(..) | CondRed(..) => {}
+            }
+        }
+        rules.retain(|(_, b, _)| !has_any(b));
+
+        loop {
+            // Many equivalences assume that functions are applied to all arguments,
+            // so they are not used on functions that are applied to more arguments.
+            let mut applied = vec![false; self.classes.len()];
+            for node in self.classes.iter().flatten() {
+                if let ENode::Op(Apply, f, _) = node {applied[self.find(*f)] = true};
+            }
+
+            let mut matches = vec![];
+            for (id, &applied) in applied.iter().enumerate() {
+                if self.find(id) != id {continue};
+                for &(a, b, eqv) in &rules {
+                    if eqv && applied {continue};
+                    for subst in self.ematch(a, &Target::Class(id), vec![]) {
+                        matches.push((id, b, subst));
+                    }
+                }
+            }
+
+            let n = self.parents.len();
+            let mut changed = false;
+            for (id, b, subst) in matches {
+                if let Ok(new) = self.instantiate(b, &subst, knowledge) {
+                    changed |= self.union(id, new);
+                }
+                if self.parents.len() > node_limit {
+                    self.rebuild();
+                    return false;
+                }
+            }
+            self.rebuild();
+            if !changed && self.parents.len() == n {return true};
+        }
+    }
+
+    /// Extracts the expression with the lowest cost from a class.
+    ///
+    /// Returns the cost and the expression.
+    pub fn extract(&self, id: ClassId, cost: &dyn Cost) -> (f64, Expr) {
+        let mut best: Vec<Option<(f64, usize)>> = vec![None; self.classes.len()];
+        loop {
+            let mut changed = false;
+            for i in 0..self.classes.len() {
+                if self.find(i) != i {continue};
+                for (j, node) in self.classes[i].iter().enumerate() {
+                    let children: Option<Vec<f64>> = node.children().into_iter()
+                        .map(|c| best[self.find(c)].map(|(c, _)| c)).collect();
+                    let children = if let Some(children) = children {children} else {continue};
+                    let c = cost.cost(node, &children);
+                    if best[i].map(|(b, _)| c < b).unwrap_or(true) {
+                        best[i] = Some((c, j));
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {break};
+        }
+        let id = self.find(id);
+        (best[id].unwrap().0, self.build(id, &best))
+    }
+
+    fn build(&self, id: ClassId, best: &[Option<(f64, usize)>]) -> Expr {
+        let id = self.find(id);
+        let child = |i: ClassId| self.build(i, best);
+        match &self.classes[id][best[id].unwrap().1] {
+            ENode::Sym(a) => Sym(a.clone()),
+            ENode::Ret(a) => Ret(a.clone()),
+            ENode::Op(op, a, b) => Op(*op, Box::new(child(*a)), Box::new(child(*b))),
+            ENode::Tup(items) => Tup(items.iter().map(|&i| child(i)).collect()),
+            ENode::List(items) => List(items.iter().map(|&i| child(i)).collect()),
+        }
+    }
+
+    /// Returns the nodes of a target.
+    fn target_nodes(&self, target: &Target) -> Vec<ENode> {
+        match target {
+            Target::Class(id) => self.nodes(*id).to_vec(),
+            Target::Tup(items) => vec![ENode::Tup(items.clone())],
+            Target::List(items) => vec![ENode::List(items.clone())],
+        }
+    }
+
+    fn canonical(&self, target: &Target) -> Target {
+        match target {
+            Target::Class(id) => Target::Class(self.find(*id)),
+            Target::Tup(items) => Target::Tup(items.iter().map(|&i| self.find(i)).collect()),
+            Target::List(items) => Target::List(items.iter().map(|&i| self.find(i)).collect()),
+        }
+    }
+
+    /// Binds a variable, checking that repeated variables are equivalent.
+    fn bind_var(&self, name: &Arc<String>, target: &Target, mut subst: Subst) -> Vec<Subst> {
+        let target = self.canonical(target);
+        match subst.iter().rev().find(|(n, _)| n == name) {
+            Some((_, old)) if self.canonical(old) == target => vec![subst],
+            Some(_) => vec![],
+            None => {
+                subst.push((name.clone(), target));
+                vec![subst]
+            }
+        }
+    }
+
+    /// Finds all ways a pattern matches expressions in a target.
+    ///
+    /// This follows `Context::bind`, except that variables bind to classes.
+    fn ematch(&self, pattern: &Expr, target: &Target, subst: Subst) -> Vec<Subst> {
+        let nodes = self.target_nodes(target);
+        let mut res = vec![];
+        match pattern {
+            Sym(Var(name)) | Sym(NoConstrVar(name)) => {
+                let no_constr = matches!(pattern, Sym(NoConstrVar(_)));
+                if nodes.iter().any(|node| match node {
+                    ENode::Tup(_) => false,
+                    ENode::Op(Constrain, _, _) => !no_constr,
+                    _ => true,
+                }) {
+                    res = self.bind_var(name, target, subst);
+                }
+            }
+            Sym(RetVar(name)) => {
+                if nodes.iter().any(|node| matches!(node, ENode::Ret(_))) {
+                    res = self.bind_var(name, target, subst);
+                }
+            }
+            Sym(Singleton(name)) => {
+                for node in &nodes {
+                    if let ENode::List(items) = node {
+                        if items.len() == 1 {
+                            let target = Target::Class(items[0]);
+                            res.extend(self.bind_var(name, &target, subst.clone()));
+                        }
+                    }
+                }
+            }
+            Sym(ListVar(name)) => {
+                if nodes.iter().any(|node| matches!(node, ENode::List(_))) {
+                    res = self.bind_var(name, target, subst);
+                }
+            }
+            Sym(HeadTailTup(head, tail)) | Sym(HeadTailList(head, tail)) => {
+                for node in &nodes {
+                    let (items, tail_target) = match (pattern, node) {
+                        (Sym(HeadTailTup(_, _)), ENode::Tup(items)) if items.len() >= 2 => {
+                            (items, if items.len() == 2 {Target::Class(items[1])}
+                                    else {Target::Tup(items[1..].into())})
+                        }
+                        (Sym(HeadTailList(_, _)), ENode::List(items)) if items.len() >= 2 => {
+                            (items, Target::List(items[1..].into()))
+                        }
+                        _ => continue,
+                    };
+                    for subst in self.ematch(head, &Target::Class(items[0]), subst.clone()) {
+                        res.extend(self.ematch(tail, &tail_target, subst));
+                    }
+                }
+            }
+            Sym(Any) => res.push(subst),
+            Sym(a) => {
+                if nodes.iter().any(|node| node == &ENode::Sym(a.clone())) {res.push(subst)};
+            }
+            Ret(a) => {
+                if nodes.iter().any(|node| node == &ENode::Ret(a.clone())) {res.push(subst)};
+            }
+            Op(op, a, b) => {
+                for node in &nodes {
+                    if let ENode::Op(op2, a2, b2) = node {
+                        if op != op2 {continue};
+                        for subst in self.ematch(a, &Target::Class(*a2), subst.clone()) {
+                            res.extend(self.ematch(b, &Target::Class(*b2), subst));
+                        }
+                    }
+                }
+            }
+            Tup(patterns) | List(patterns) => {
+                for node in &nodes {
+                    let items = match (pattern, node) {
+                        (Tup(_), ENode::Tup(items)) | (List(_), ENode::List(items))
+                            if items.len() == patterns.len() => items,
+                        _ => continue,
+                    };
+                    let mut substs = vec![subst.clone()];
+                    for (p, &i) in patterns.iter().zip(items.iter()) {
+                        substs = substs.into_iter()
+                            .flat_map(|subst| self.ematch(p, &Target::Class(i), subst))
+                            .collect();
+                    }
+                    res.extend(substs);
+                }
+            }
+        }
+        res
+    }
+
+    /// Adds the right side of a rule, using classes bound to variables.
+    fn instantiate(
+        &mut self,
+        expr: &Expr,
+        subst: &Subst,
+        knowledge: &[Knowledge]
+    ) -> Result<ClassId, Error> {
+        Ok(match expr {
+            Sym(Var(name)) => {
+                match subst.iter().rev().find(|(n, _)| n == name) {
+                    Some((_, Target::Class(id))) => *id,
+                    Some((_, Target::Tup(items))) => self.add(ENode::Tup(items.clone())),
+                    Some((_, Target::List(items))) => self.add(ENode::List(items.clone())),
+                    None => return Err(Error::CouldNotFind(name.clone())),
+                }
+            }
+            Sym(UnopRetVar(..)) | Sym(BinopRetVar(..)) => {
+                // Computes using the cheapest expressions of the bound classes.
+                let mut ctx = Context::new();
+                for (name, target) in subst {
+                    let id = match target {
+                        Target::Class(id) => *id,
+                        Target::Tup(items) => self.add(ENode::Tup(items.clone())),
+                        Target::List(items) => self.add(ENode::List(items.clone())),
+                    };
+                    let value = match self.nodes(id).iter().find(|n| matches!(n, ENode::Ret(_))) {
+                        Some(ENode::Ret(v)) => Ret(v.clone()),
+                        _ => self.extract(id, &Size).1,
+                    };
+                    ctx.vars.push((name.clone(), value));
+                }
+                let expr = ctx.substitute_in(expr, knowledge)?;
+                self.add_expr(&expr)
+            }
+            Sym(a) => self.add(ENode::Sym(a.clone())),
+            Ret(a) => self.add(ENode::Ret(a.clone())),
+            Op(op, a, b) => {
+                let a = self.instantiate(a, subst, knowledge)?;
+                let b = self.instantiate(b, subst, knowledge)?;
+                self.add(ENode::Op(*op, a, b))
+            }
+            Tup(items) => {
+                let items = items.iter().map(|n| self.instantiate(n, subst, knowledge))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.add(ENode::Tup(items))
+            }
+            List(items) => {
+                let items = items.iter().map(|n| self.instantiate(n, subst, knowledge))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.add(ENode::List(items))
+            }
+        })
+    }
+}
+
+/// Returns `true` if an expression contains the wildcard `_`.
+///
+/// Such expressions can only be used as patterns.
+pub(crate) fn has_any(expr: &Expr) -> bool {
+    match expr {
+        Sym(Any) => true,
+        _ => expr.children().into_iter().any(has_any),
+    }
+}
@@ -0,0 +1,64 @@
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+
+/// The version of the encoding, which is stored with encoded data.
+///
+/// This is increased when the encoding of some type changes.
+pub const ENCODING_VERSION: u32 = 1;
+
+/// Data tagged with the version of its encoding.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Versioned<T> {
+    /// The version of the encoding.
+    pub version: u32,
+    /// The data.
+    pub data: T,
+}
+
+impl<T> Versioned<T> {
+    fn check(self) -> Result<T, String> {
+        if self.version == ENCODING_VERSION {Ok(self.data)} else {
+            Err(format!("Expected encoding version {}, found {}", ENCODING_VERSION, self.version))
+        }
+    }
+}
+
+/// Encodes data as JSON, tagged with the encoding version.
+///
+/// The schema is `{"version": 1, "data": ...}`, where enums are tagged by variant name:
+///
+/// - Variants without data are strings, e.g. `"Not"` or `"Apply"`
+/// - Other variants are objects with one key, e.g. `{"Sym": "Not"}` or `{"Ret": {"F64": 2.0}}`
+/// - Variants with several fields store them in an array,
+///   e.g. `{"Op": ["Apply", {"Sym": "Not"}, {"Ret": {"Bool": true}}]}`
+/// - Names are strings, e.g. `{"Sym": {"Var": "x"}}`
+/// - Structs are objects with field names, e.g. `{"expr": ..., "justification": ...}`
+///
+/// Numbers that are not finite can not be encoded as JSON.
+pub fn to_json<T: Serialize>(data: &T) -> Result<String, String> {
+    serde_json::to_string(&Versioned {version: ENCODING_VERSION, data})
+        .map_err(|err| format!("Could not encode JSON, {}", err))
+}
+
+/// Decodes data from JSON, see `to_json`.
+pub fn from_json<T: DeserializeOwned>(json: &str) -> Result<T, String> {
+    serde_json::from_str::<Versioned<T>>(json)
+        .map_err(|err| format!("Could not decode JSON, {}", err))?
+        .check()
+}
+
+/// Encodes data in a binary format, tagged with the encoding version.
+///
+/// This uses the default options of `bincode` 1.x,
+/// with the same structure as `to_json`.
+pub fn to_binary<T: Serialize>(data: &T) -> Result<Vec<u8>, String> {
+    bincode::serialize(&Versioned {version: ENCODING_VERSION, data})
+        .map_err(|err| format!("Could not encode binary, {}", err))
+}
+
+/// Decodes data from the binary format, see `to_binary`.
+pub fn from_binary<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+    bincode::deserialize::<Versioned<T>>(bytes)
+        .map_err(|err| format!("Could not decode binary, {}", err))?
+        .check()
+}
@@ -3,7 +3,8 @@ use std::fmt;
 use super::*;
 
 /// Function expression.
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, PartialOrd, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Expr {
     /// A symbol that is used together with symbolic knowledge.
     Sym(Symbol),
@@ -19,6 +20,17 @@ pub enum Expr {
     List(Vec<Expr>),
 }
 
+impl Expr {
+    /// Returns a hash of the expression, for looking up equal expressions.
+    pub(crate) fn hash_u64(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        self.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
 impl fmt::Display for Expr {
     fn fmt(&self, w: &mut fmt::Formatter<'_>) -> std::result::Result<(), fmt::Error> {
         match self {
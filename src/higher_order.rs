@@ -0,0 +1,120 @@
+use super::*;
+
+impl Context {
+    /// Binds a higher-order pattern variable `f` applied to the variables `args`.
+    ///
+    /// The function is bound to the value where the values of the arguments are holes.
+    /// Fails with `Mismatch::Unbound` when an argument is not bound yet,
+    /// see `bind_deferred`.
+    pub(crate) fn bind_ho_var<'a>(
+        &mut self,
+        f: &'a Arc<String>,
+        args: &'a [Arc<String>],
+        value: &Expr
+    ) -> Result<(), Fail<'a>> {
+        let mut holes: Vec<Option<Expr>> = vec![];
+        for a in args {
+            match self.lookup(a) {
+                Some(x) => holes.push(Some(x.clone())),
+                None => return Err(Fail::new(FailKind::Unbound(a))),
+            }
+        }
+        let body = self.abstract_holes(value, &holes);
+        if let Some(old) = self.lookup(f) {
+            if self.equal(old, &body) {return Ok(())};
+            let conflict = Mismatch::Conflict(f.clone(), old.clone(), body);
+            return Err(Fail::new(FailKind::Owned(conflict)));
+        }
+        self.vars.push((f.clone(), body));
+        Ok(())
+    }
+
+    /// Binds a pattern in two passes, such that the arguments of higher-order pattern variables
+    /// are bound before the variables themselves, regardless of the order in the pattern.
+    ///
+    /// The first pass binds the pattern where higher-order pattern variables are wildcards.
+    /// The context is restored to `n` variables before binding.
+    pub(crate) fn bind_deferred(
+        &mut self,
+        name: &Expr,
+        value: &Expr,
+        n: usize
+    ) -> Result<(), WhyNot> {
+        self.vars.truncate(n);
+        let first_order = without_ho_vars(name);
+        for pattern in [&first_order, name] {
+            if let Err(fail) = self.try_bind(pattern, value) {return Err(fail.why_not(self))};
+        }
+        Ok(())
+    }
+
+    /// Substitutes a higher-order pattern variable `f` applied to the variables `args`,
+    /// by filling the holes of its context with the values of the arguments.
+    pub(crate) fn substitute_ho_var(
+        &self,
+        f: &Arc<String>,
+        args: &[Arc<String>]
+    ) -> Result<Expr, Error> {
+        let body = self.lookup(f).ok_or_else(|| Error::CouldNotFind(f.clone()))?;
+        let values = args.iter()
+            .map(|a| self.lookup(a).cloned().ok_or_else(|| Error::CouldNotFind(a.clone())))
+            .collect::<Result<Vec<Expr>, Error>>()?;
+        fill_holes(body, &values)
+    }
+
+    /// Returns the last value bound to a variable.
+    fn lookup(&self, name: &Arc<String>) -> Option<&Expr> {
+        self.vars.iter().rev().find(|(n, _)| n == name).map(|(_, v)| v)
+    }
+
+    /// Replaces sub-expressions equal to some value with a hole,
+    /// preferring the largest sub-expressions.
+    fn abstract_holes(&self, expr: &Expr, holes: &[Option<Expr>]) -> Expr {
+        let hole = holes.iter().position(|h| h.as_ref().map(|h| self.equal(h, expr)) == Some(true));
+        if let Some(i) = hole {
+            return Sym(Hole(i));
+        }
+        match expr {
+            Sym(_) | Ret(_) => expr.clone(),
+            Op(op, a, b) => Op(*op, Box::new(self.abstract_holes(a, holes)),
+                               Box::new(self.abstract_holes(b, holes))),
+            Tup(items) => Tup(items.iter().map(|n| self.abstract_holes(n, holes)).collect()),
+            List(items) => List(items.iter().map(|n| self.abstract_holes(n, holes)).collect()),
+        }
+    }
+}
+
+/// Fills the holes of a context with values.
+fn fill_holes(expr: &Expr, values: &[Expr]) -> Result<Expr, Error> {
+    Ok(match expr {
+        Sym(Hole(i)) => values.get(*i).ok_or(Error::InvalidComputation)?.clone(),
+        Sym(_) | Ret(_) => expr.clone(),
+        Op(op, a, b) => Op(*op, Box::new(fill_holes(a, values)?), Box::new(fill_holes(b, values)?)),
+        Tup(items) => Tup(items.iter().map(|n| fill_holes(n, values)).collect::<Result<_, _>>()?),
+        List(items) => List(items.iter().map(|n| fill_holes(n, values)).collect::<Result<_, _>>()?),
+    })
+}
+
+/// Returns `true` if an expression contains holes of a higher-order pattern variable.
+pub(crate) fn has_holes(expr: &Expr) -> bool {
+    match expr {
+        Sym(Hole(_)) => true,
+        Sym(_) | Ret(_) => false,
+        _ => expr.children().into_iter().any(has_holes),
+    }
+}
+
+/// Replaces higher-order pattern variables with wildcards.
+fn without_ho_vars(expr: &Expr) -> Expr {
+    match expr {
+        Sym(HoVar(..)) => Sym(Any),
+        Sym(HeadTailTup(a, b)) =>
+            Sym(HeadTailTup(Box::new(without_ho_vars(a)), Box::new(without_ho_vars(b)))),
+        Sym(HeadTailList(a, b)) =>
+            Sym(HeadTailList(Box::new(without_ho_vars(a)), Box::new(without_ho_vars(b)))),
+        Sym(_) | Ret(_) => expr.clone(),
+        Op(op, a, b) => Op(*op, Box::new(without_ho_vars(a)), Box::new(without_ho_vars(b))),
+        Tup(items) => Tup(items.iter().map(without_ho_vars).collect()),
+        List(items) => List(items.iter().map(without_ho_vars).collect()),
+    }
+}
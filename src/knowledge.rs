@@ -3,6 +3,8 @@ use std::fmt;
 use super::*;
 
 /// Represents knowledge about symbols.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Knowledge {
     /// A symbol has some definition.
     Def(Symbol, Expr),
@@ -10,6 +12,11 @@ pub enum Knowledge {
     Red(Expr, Expr),
     /// Two expressions that are equivalent but neither normalizes the other.
     Eqv(Expr, Expr),
+    /// A reduction that only applies when a condition evaluates to `\true`.
+    ///
+    /// Conditions can be discharged by reductions in the knowledge base,
+    /// e.g. `invertible(not) => \true`, or by a user predicate, see `Expr::reduce_with_discharge`.
+    CondRed(Expr, Expr, Expr),
 }
 
 impl fmt::Display for Knowledge {
@@ -20,6 +27,7 @@ impl fmt::Display for Knowledge {
             Def(a, b) => write!(w, "{} := {}", a, b)?,
             Red(a, b) => write!(w, "{} => {}", a, b)?,
             Eqv(a, b) => write!(w, "{} <=> {}", a, b)?,
+            CondRed(a, b, c) => write!(w, "{} => {} when {}", a, b, c)?,
         }
         Ok(())
     }
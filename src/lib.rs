@@ -34,14 +34,26 @@
 //! (len · concat)(a)(b)
 //! (concat[len] · (len · fst, len · snd))(a)(b)
 //! (add · (len · fst, len · snd))(a)(b)
-//! <=>  add((len · fst)(a)(b), (len · snd)(a)(b))
-//! > add((len · fst)(a)(b), (len · snd)(a)(b))
-//! add((len · fst)(a)(b), (len · snd)(a)(b))
+//! <=> 0  add((len · fst)(a)(b))((len · snd)(a)(b))
+//! <=> 1  add((len · fst)(a))((len · snd)(a))(b)
+//! <=> 2  (add · (len · fst, len · snd)(a))(b)
+//! > eqv 0
 //! add((len · fst)(a)(b))((len · snd)(a)(b))
 //! add(len(a))((len · snd)(a)(b))
 //! add(len(a))(len(b))
 //! ```
 //!
+//! Poi can also find the faster form by itself, using a cost model where `concat` is expensive:
+//!
+//! ```rust
+//! use poi::*;
+//!
+//! let expr = parse_str("(len . concat)(a, b)").unwrap();
+//! let cost = CostModel::new().symbol(Symbol::Concat, 10.0);
+//! let (_, trace) = simplify(&expr, &std(), &cost);
+//! assert_eq!(format!("{}", trace.result()), "add(len(a))(len(b))");
+//! ```
+//!
 //! ### Introduction to Poi and Path Semantics
 //!
 //! In "point-free" or "tacit" programming, functions do not identify the arguments
@@ -260,6 +272,8 @@
 //!     Red(Expr, Expr),
 //!     /// Two expressions that are equivalent but neither normalizes the other.
 //!     Eqv(Expr, Expr),
+//!     /// A reduction that only applies when a condition evaluates to `\true`.
+//!     CondRed(Expr, Expr, Expr),
 //! }
 //! ```
 //!
@@ -276,6 +290,9 @@
 //! This is used when it is not clear which direction one should go.
 //! This rule is bi-directional, which means one can treat it as a reduction both ways.
 //!
+//! The `CondRed` variant is a reduction with a side condition, e.g. `div(x)(x) => \1 when not(eq(x, \0))`.
+//! The condition is evaluated with the bound variables and the rule only applies when it is `\true`.
+//!
 
 use std::sync::Arc;
 
@@ -292,6 +309,23 @@ pub use sym::*;
 pub use standard_library::*;
 pub use parsing::*;
 pub use knowledge::*;
+pub use mismatch::*;
+pub use strategy::*;
+pub use pos::*;
+pub use tactic::*;
+pub use egraph::*;
+pub use cost::*;
+pub use simplify::*;
+pub use ac::*;
+pub use rule::*;
+pub use lint::*;
+pub use redex::*;
+pub use query::*;
+pub use unify::*;
+pub use boolean::*;
+pub use condition::*;
+#[cfg(feature = "serde")]
+pub use encoding::*;
 
 mod val;
 mod expr;
@@ -301,6 +335,26 @@ mod knowledge;
 mod standard_library;
 mod parsing;
 mod arity;
+mod mismatch;
+mod strategy;
+mod pos;
+mod tactic;
+mod egraph;
+mod cost;
+mod simplify;
+mod ac;
+mod higher_order;
+mod condition;
+mod rule;
+mod lint;
+mod redex;
+mod query;
+mod unify;
+mod boolean;
+#[cfg(feature = "serde")]
+mod encoding;
+
+pub mod theory;
 
 /// Used to global import enum variants.
 pub mod prelude {
@@ -335,7 +389,11 @@ impl<T0, T1, T2> Into<Expr> for (T0, T1, T2)
 impl Expr {
     /// Returns available equivalences of the expression, using a knowledge base.
     pub fn equivalences(&self, knowledge: &[Knowledge]) -> Vec<(Expr, usize)> {
-        let mut ctx = Context {vars: vec![]};
+        self.equivalences_ac(knowledge, &ac_symbols(knowledge))
+    }
+
+    fn equivalences_ac(&self, knowledge: &[Knowledge], ac: &[Symbol]) -> Vec<(Expr, usize)> {
+        let mut ctx = Context::with_ac(ac);
         let mut res = vec![];
         for i in 0..knowledge.len() {
             if let Eqv(a, b) = &knowledge[i] {
@@ -354,16 +412,16 @@ impl Expr {
         match self {
             Sym(_) | Ret(_) => {}
             Op(op, a, b) => {
-                for (ea, i) in a.equivalences(knowledge).into_iter() {
+                for (ea, i) in a.equivalences_ac(knowledge, ac).into_iter() {
                     res.push((Op(*op, Box::new(ea), b.clone()), i));
                 }
-                for (eb, i) in b.equivalences(knowledge).into_iter() {
+                for (eb, i) in b.equivalences_ac(knowledge, ac).into_iter() {
                     res.push((Op(*op, a.clone(), Box::new(eb)), i));
                 }
             }
             Tup(items) | List(items) => {
                 for i in 0..items.len() {
-                    for (expr, j) in items[i].equivalences(knowledge).into_iter() {
+                    for (expr, j) in items[i].equivalences_ac(knowledge, ac).into_iter() {
                         let mut new_items: Vec<Expr> = items[0..i].into();
                         new_items.push(expr);
                         new_items.extend(items[i+1..].iter().map(|n| n.clone()));
@@ -384,46 +442,37 @@ impl Expr {
     ///
     /// This combines reductions and inlining of all symbols.
     pub fn eval(&self, knowledge: &[Knowledge]) -> Result<Expr, Error> {
-        let mut me = self.clone();
-        loop {
-            let expr = me.reduce_all(knowledge).inline_all(knowledge)?;
-            if expr == me {break};
-            me = expr;
-        }
-        Ok(me)
+        self.eval_with(knowledge, Strategy::Standard)
     }
 
     /// Reduces an expression using a knowledge base, until it can not be reduces further.
     pub fn reduce_all(&self, knowledge: &[Knowledge]) -> Expr {
-        let mut me = self.clone();
-        while let Ok((expr, _)) = me.reduce(knowledge) {me = expr}
-        me
+        self.reduce_all_with(knowledge, Strategy::Standard)
     }
 
     /// Reduces expression one step using a knowledge base.
+    ///
+    /// This uses the standard strategy, see `Strategy::Standard`.
     pub fn reduce(&self, knowledge: &[Knowledge]) -> Result<(Expr, usize), Error> {
-        let mut ctx = Context {vars: vec![]};
-        let mut me: Result<(Expr, usize), Error> = Err(Error::NoReductionRule);
-        for i in 0..knowledge.len() {
-            if let Red(a, b) = &knowledge[i] {
-                if ctx.bind(a, self) {
-                    me = match ctx.substitute(b) {
-                        Ok(expr) => Ok((expr, i)),
-                        Err(err) => Err(err),
-                    };
-                    break;
-                }
-            }
-        }
+        self.reduce_env(knowledge, &Env::new(knowledge))
+    }
+
+    /// Reduces expression one step in an environment.
+    pub(crate) fn reduce_env(
+        &self,
+        knowledge: &[Knowledge],
+        env: &Env
+    ) -> Result<(Expr, usize), Error> {
+        let me = self.reduce_root_env(knowledge, env);
 
         match self {
             Op(op, a, b) => {
-                if let Ok((a, i)) = a.reduce(knowledge) {
+                if let Ok((a, i)) = a.reduce_env(knowledge, env) {
                     // Prefer the reduction that matches the first rule.
                     if let Ok((expr, j)) = me {if j < i {return Ok((expr, j))}};
                     return Ok((Op(*op, Box::new(a), b.clone()), i));
                 }
-                if let Ok((b, i)) = b.reduce(knowledge) {
+                if let Ok((b, i)) = b.reduce_env(knowledge, env) {
                     // Prefer the reduction that matches the first rule.
                     if let Ok((expr, j)) = me {if j < i {return Ok((expr, j))}};
                     return Ok((Op(*op, a.clone(), Box::new(b)), i));
@@ -432,7 +481,7 @@ impl Expr {
             Tup(a) | List(a) => {
                 let mut res = vec![];
                 for i in 0..a.len() {
-                    if let Ok((n, j)) = a[i].reduce(knowledge) {
+                    if let Ok((n, j)) = a[i].reduce_env(knowledge, env) {
                         // Prefer the reduction that matches the first rule.
                         if let Ok((expr, k)) = me {if k < j {return Ok((expr, k))}};
                         res.push(n);
@@ -455,6 +504,47 @@ impl Expr {
         me
     }
 
+    /// Reduces expression one step at the root, using the first matching rule.
+    ///
+    /// Sub-expressions are not reduced.
+    pub fn reduce_root(&self, knowledge: &[Knowledge]) -> Result<(Expr, usize), Error> {
+        self.reduce_root_env(knowledge, &Env::new(knowledge))
+    }
+
+    /// Reduces expression one step at the root in an environment.
+    pub(crate) fn reduce_root_env(
+        &self,
+        knowledge: &[Knowledge],
+        env: &Env
+    ) -> Result<(Expr, usize), Error> {
+        let mut ctx = Context::with_ac(&env.ac);
+        for (i, k) in knowledge.iter().enumerate() {
+            if let Red(a, b) = k {
+                if ctx.bind(a, self) {
+                    match ctx.substitute_in(b, knowledge) {
+                        Ok(expr) => return Ok((expr, i)),
+                        Err(Error::NotApplicable) => ctx.vars.clear(),
+                        Err(err) => return Err(err),
+                    }
+                }
+            } else if let CondRed(a, b, c) = k {
+                if ctx.bind(a, self) {
+                    match ctx.condition_env(c, knowledge, env) {
+                        Ok(_) => match ctx.substitute_in(b, knowledge) {
+                            Ok(expr) => return Ok((expr, i)),
+                            Err(Error::NotApplicable) => {}
+                            Err(err) => return Err(err),
+                        },
+                        Err(Error::ConditionTooDeep) => return Err(Error::ConditionTooDeep),
+                        Err(_) => {}
+                    }
+                    ctx.vars.clear();
+                }
+            }
+        }
+        Err(Error::NoReductionRule)
+    }
+
     /// Inlines all symbols using a knowledge base.
     ///
     /// Ignores missing definitions in domain constraints.
@@ -582,64 +672,109 @@ impl Expr {
 }
 
 /// Stores variables bound by context.
+#[derive(Default)]
 pub struct Context {
     /// Contains the variables in the context.
     pub vars: Vec<(Arc<String>, Expr)>,
+    /// Symbols that are matched modulo associativity and commutativity.
+    pub(crate) ac: Vec<Symbol>,
 }
 
 impl Context {
+    /// Creates a new context that matches syntactically.
+    pub fn new() -> Context {Context::default()}
+
+    /// Creates a new context that matches some symbols modulo associativity and commutativity.
+    ///
+    /// The symbols of a knowledge base are given by `ac_symbols`.
+    /// Every operand must be matched, so a pattern like `add(\x)(\y)` does not match
+    /// inside a longer sum, unless it has a variable for the other operands,
+    /// e.g. `add(\x)(add(\y)(z))`.
+    pub fn with_ac(ac: &[Symbol]) -> Context {
+        Context {vars: vec![], ac: ac.to_vec()}
+    }
+
     /// Binds patterns of a `name` expression to a `value` expression.
     pub fn bind(&mut self, name: &Expr, value: &Expr) -> bool {
+        let n = self.vars.len();
+        let ok = match self.try_bind(name, value) {
+            Ok(()) => true,
+            Err(fail) if fail.is_unbound() => self.bind_deferred(name, value, n).is_ok(),
+            Err(_) => false,
+        };
+        if !ok {self.vars.clear()};
+        ok
+    }
+
+    /// Binds patterns of a `name` expression to a `value` expression,
+    /// reporting the first mismatch when failing.
+    pub fn bind_or_mismatch(&mut self, name: &Expr, value: &Expr) -> Result<(), WhyNot> {
+        let n = self.vars.len();
+        let res = match self.try_bind(name, value) {
+            Ok(()) => Ok(()),
+            Err(fail) if fail.is_unbound() => self.bind_deferred(name, value, n),
+            Err(fail) => Err(fail.why_not(self)),
+        };
+        if res.is_err() {self.vars.clear()};
+        res
+    }
+
+    fn try_bind<'a>(&mut self, name: &'a Expr, value: &'a Expr) -> Result<(), Fail<'a>> {
+        let n = self.vars.len();
+        match self.try_bind_plain(name, value) {
+            Ok(()) => Ok(()),
+            Err(fail) => self.try_bind_ac(name, value, n, fail),
+        }
+    }
+
+    fn try_bind_plain<'a>(&mut self, name: &'a Expr, value: &'a Expr) -> Result<(), Fail<'a>> {
         match (name, value) {
-            (Sym(NoConstrVar(_)), v) if v.has_constraint(1) => {
-                self.vars.clear();
-                false
+            (Sym(NoConstrVar(name)), v) if v.has_constraint(1) => {
+                Err(Fail::new(FailKind::HasConstraint(name, v)))
             }
             (Sym(Var(_)), Tup(_)) | (Sym(NoConstrVar(_)), Tup(_)) => {
-                self.vars.clear();
-                false
+                Err(Fail::new(FailKind::Differ(name, value)))
             }
             (Sym(Var(name)), x) | (Sym(NoConstrVar(name)), x) => {
                 for i in (0..self.vars.len()).rev() {
                     if &self.vars[i].0 == name {
-                        if &self.vars[i].1 == x {
+                        if self.equal(&self.vars[i].1, x) {
                             break
                         } else {
-                            self.vars.clear();
-                            return false;
+                            return Err(Fail::new(FailKind::Conflict(name, i, x)));
                         }
                     }
                 }
                 self.vars.push((name.clone(), x.clone()));
-                true
+                Ok(())
             }
+            (Sym(HoVar(f, args)), x) => self.bind_ho_var(f, args, x),
             (Sym(RetVar(name)), Ret(_)) => {
                 for i in (0..self.vars.len()).rev() {
                     if &self.vars[i].0 == name {
                         if &self.vars[i].1 == value {
                             break
                         } else {
-                            self.vars.clear();
-                            return false;
+                            return Err(Fail::new(FailKind::Conflict(name, i, value)));
                         }
                     }
                 }
                 self.vars.push((name.clone(), value.clone()));
-                true
+                Ok(())
             }
             (Sym(Singleton(name)), List(x)) if x.len() == 1 => {
                 self.vars.push((name.clone(), x[0].clone()));
-                true
+                Ok(())
             }
             (Sym(ListVar(name)), List(_)) => {
                 self.vars.push((name.clone(), value.clone()));
-                true
+                Ok(())
             }
             (Sym(HeadTailTup(head, tail)), Tup(list)) |
             (Sym(HeadTailList(head, tail)), List(list)) => {
-                if list.len() < 2 {return false};
+                if list.len() < 2 {return Err(Fail::new(FailKind::Length(name, value)))};
 
-                let r = self.bind(head, &list[0]);
+                self.try_bind(head, &list[0]).map_err(|fail| fail.at(0))?;
                 let b: Expr = if let (Sym(HeadTailTup(_, _)), Tup(_)) = (name, value) {
                     if list[1..].len() == 1 {
                         list[1].clone()
@@ -650,58 +785,56 @@ impl Context {
                     List(list[1..].into())
                 };
 
-                let r = r && self.bind(tail, &b);
-                if !r {self.vars.clear()};
-                r
+                // The tail is a new expression, so the mismatch is stored by value.
+                match self.try_bind(tail, &b) {
+                    Ok(()) => Ok(()),
+                    Err(fail) => Err(Fail::new(FailKind::Owned(fail.why_not(self).mismatch))),
+                }
             }
-            (Sym(Any), _) => true,
-            (Sym(a), Sym(b)) if a == b => true,
-            (Ret(a), Ret(b)) if a == b => true,
+            (Sym(Any), _) => Ok(()),
+            (Sym(a), Sym(b)) if a == b => Ok(()),
+            (Ret(a), Ret(b)) if a == b => Ok(()),
             (Op(op1, a1, b1), Op(op2, a2, b2)) if op1 == op2 => {
-                let r = self.bind(a1, a2) && self.bind(b1, b2);
-                if !r {self.vars.clear()};
-                r
+                self.try_bind(a1, a2).map_err(|fail| fail.at(0))?;
+                self.try_bind(b1, b2).map_err(|fail| fail.at(1))
             }
-            (Tup(a), Tup(b)) if a.len() == b.len() => {
-                let mut all = true;
-                for i in 0..a.len() {
-                    let r = self.bind(&a[i], &b[i]);
-                    if !r {
-                        all = false;
-                        break;
-                    }
-                }
-                if !all {self.vars.clear()};
-                all
+            (Tup(a), Tup(b)) | (List(a), List(b)) if a.len() != b.len() => {
+                Err(Fail::new(FailKind::Length(name, value)))
             }
-            (List(a), List(b)) if a.len() == b.len() => {
-                let mut all = true;
+            (Tup(a), Tup(b)) | (List(a), List(b)) => {
                 for i in 0..a.len() {
-                    let r = self.bind(&a[i], &b[i]);
-                    if !r {
-                        all = false;
-                        break;
-                    }
+                    self.try_bind(&a[i], &b[i]).map_err(|fail| fail.at(i))?;
                 }
-                if !all {self.vars.clear()};
-                all
-            }
-            _ => {
-                self.vars.clear();
-                false
+                Ok(())
             }
+            _ => Err(Fail::new(FailKind::Differ(name, value))),
         }
     }
 
     /// Substitute free occurences of variables in context.
     ///
     /// This is used on the right side in a reduction rule.
+    /// Existential paths `compute::ex(f)` need a knowledge base, see `substitute_in`.
     pub fn substitute(&self, x: &Expr) -> Result<Expr, Error> {
+        self.substitute_in(x, &[])
+    }
+
+    /// Substitute free occurences of variables in context,
+    /// computing existential paths `compute::ex(f)` using a knowledge base.
+    ///
+    /// Returns `Error::NotApplicable` when `f` is not a boolean function in the knowledge base,
+    /// so the rule does not apply.
+    /// Higher-order pattern variables must be applied to arguments, e.g. `$F(x)` instead of `F`.
+    pub fn substitute_in(&self, x: &Expr, knowledge: &[Knowledge]) -> Result<Expr, Error> {
         match x {
             Sym(Var(name)) => {
                 for i in (0..self.vars.len()).rev() {
                     if &self.vars[i].0 == name {
-                        return Ok(self.vars[i].1.clone())
+                        let value = &self.vars[i].1;
+                        if higher_order::has_holes(value) {
+                            return Err(Error::UnappliedHoVar(name.clone()))
+                        };
+                        return Ok(value.clone())
                     }
                 }
                 Err(Error::CouldNotFind(name.clone()))
@@ -726,6 +859,9 @@ impl Context {
                             _ => return Err(Error::InvalidComputation),
                         })
                     }
+                    Some(a) if **f == Ex => {
+                        existential_path(&a, knowledge).map_err(|_| Error::NotApplicable)
+                    }
                     _ => Err(Error::CouldNotFind(a.clone())),
                 }
             }
@@ -799,21 +935,23 @@ impl Context {
                     }
                 }
             }
+            Sym(HoVar(f, args)) => self.substitute_ho_var(f, args),
             Sym(_) | Ret(_) => Ok(x.clone()),
             Op(op, a, b) => {
-                Ok(Op(*op, Box::new(self.substitute(a)?), Box::new(self.substitute(b)?)))
+                let (a, b) = (self.substitute_in(a, knowledge)?, self.substitute_in(b, knowledge)?);
+                Ok(Op(*op, Box::new(a), Box::new(b)))
             }
             Tup(a) => {
                 let mut res = vec![];
                 for i in 0..a.len() {
-                    res.push(self.substitute(&a[i])?);
+                    res.push(self.substitute_in(&a[i], knowledge)?);
                 }
                 Ok(Tup(res))
             }
             List(a) => {
                 let mut res = vec![];
                 for i in 0..a.len() {
-                    res.push(self.substitute(&a[i])?);
+                    res.push(self.substitute_in(&a[i], knowledge)?);
                 }
                 Ok(List(res))
             }
@@ -822,7 +960,8 @@ impl Context {
 }
 
 /// Represents an error.
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Error {
     /// Invalid function for computing something from left side of expression to right side.
     InvalidComputation,
@@ -832,6 +971,34 @@ pub enum Error {
     NoReductionRule,
     /// Could not find variable.
     CouldNotFind(Arc<String>),
+    /// A higher-order pattern variable is used without arguments, e.g. `F` instead of `$F(x)`.
+    UnappliedHoVar(Arc<String>),
+    /// There was no sub-expression at the position.
+    InvalidPosition,
+    /// The rule can not be used in this way, e.g. a definition or a reduction used backwards.
+    InvalidRule,
+    /// The rule was not found in the knowledge base.
+    UnknownRule,
+    /// The expression did not change.
+    NoProgress,
+    /// The condition of a conditional reduction does not hold.
+    ConditionDoesNotHold,
+    /// Checking a condition needs too many nested conditions, see `CONDITION_MAX_DEPTH`.
+    ConditionTooDeep,
+    /// There is no unique normal path.
+    NoPath,
+    /// The rule does not apply, e.g. `compute::ex(f)` when `f` is not a boolean function.
+    NotApplicable,
+    /// A definition refers to itself, so it can not be evaluated as a boolean function.
+    CyclicDefinition,
+    /// Boolean functions take different numbers of arguments.
+    ArityMismatch,
+    /// A boolean function takes more arguments than `BOOLEAN_MAX_ARITY`.
+    TooManyArguments,
+    /// A proof step does not follow from the expression before it.
+    ///
+    /// Stores the index of the step.
+    InvalidStep(usize),
 }
 
 impl Into<Expr> for Symbol {
@@ -918,6 +1085,11 @@ pub fn no_constr<A: Into<String>>(a: A) -> Expr {
     Sym(NoConstrVar(Arc::new(a.into())))
 }
 
+/// A higher-order pattern variable `f` applied to distinct variables, e.g. `$F(x)`.
+pub fn ho_var<A: Into<String>>(f: A, args: &[&str]) -> Expr {
+    Sym(HoVar(Arc::new(f.into()), args.iter().map(|a| Arc::new(a.to_string())).collect()))
+}
+
 /// A 2D vector.
 pub fn vec2<A: Into<Expr>, B: Into<Expr>>(a: A, b: B) -> Expr {List(vec![a.into(), b.into()])}
 
@@ -935,6 +1107,8 @@ pub fn concrete_op<S: Into<Symbol>>(s: S) -> Knowledge {
 }
 
 /// Knowledge about a commuative binary operator.
+///
+/// Symbols that are also `associative` are matched modulo associativity and commutativity.
 pub fn commutative<S: Into<Symbol>>(s: S) -> Knowledge {
     let s = s.into();
     let a: Expr = "a".into();
@@ -1025,4 +1199,549 @@ mod tests {
         let a = a.reduce(std).unwrap().0;
         assert_eq!(a, true.into());
     }
+
+    #[test]
+    fn why_not() {
+        let std = vec![
+            Red(path(And, Not), Or.into()),
+            Red(app2(Add, "x", "x"), app2(Mul, 2.0, "x")),
+            Red(app(no_constr("f"), Tup(vec!["x".into(), "y".into()])), "f".into()),
+            Def(Not, _if(false, true)),
+        ];
+
+        let a = path(And, Not);
+        assert_eq!(a.why_not(&std, 0), None);
+
+        let a = path(And, Id);
+        let b = a.why_not(&std, 0).unwrap();
+        assert_eq!(b.pos, Pos(vec![1]));
+        assert_eq!(b.mismatch, Mismatch::Differ(Not.into(), Id.into()));
+
+        let a = app2(Add, "a", "b");
+        let b = a.why_not(&std, 1).unwrap();
+        assert_eq!(b.pos, Pos(vec![1]));
+        assert_eq!(b.mismatch, Mismatch::Conflict(Arc::new("x".into()), "a".into(), "b".into()));
+
+        let a = app(And, Tup(vec![true.into(), true.into(), true.into()]));
+        let b = a.why_not(&std, 2).unwrap();
+        assert_eq!(b.pos, Pos(vec![1]));
+        if let Mismatch::Length(_, _) = b.mismatch {} else {panic!()}
+
+        let a = app(constr(And, Eq), Tup(vec![true.into(), true.into()]));
+        let b = a.why_not(&std, 2).unwrap();
+        assert_eq!(b.pos, Pos(vec![0]));
+        if let Mismatch::HasConstraint(_, _) = b.mismatch {} else {panic!()}
+
+        assert_eq!(a.why_not(&std, 3).unwrap().mismatch, Mismatch::NotRule);
+    }
+
+    #[test]
+    fn strategies() {
+        use Strategy::*;
+
+        let std = vec![
+            Red(app(Not, true), false.into()),
+            Red(app(Not, app(Not, "x")), "x".into()),
+        ];
+        let a = app(Not, app(Not, true));
+        let reduce = |s| a.reduce_with(&std, s).unwrap();
+        assert_eq!(reduce(Standard), (app(Not, false), 0));
+        assert_eq!(reduce(LeftmostInnermost), (app(Not, false), 0));
+        assert_eq!(reduce(LeftmostOutermost), (true.into(), 1));
+        assert_eq!(reduce(ParallelOutermost), (true.into(), 1));
+        assert_eq!(reduce(RulePriority), (app(Not, false), 0));
+
+        let a: Expr = (app(Not, true), app(Not, app(Not, false))).into();
+        assert_eq!(a.reduce_with(&std, ParallelOutermost).unwrap(), ((false, false).into(), 0));
+        assert_eq!(a.reduce_with(&std, LeftmostOutermost).unwrap(),
+                   ((false, app(Not, app(Not, false))).into(), 0));
+    }
+
+    #[test]
+    fn rewrite_at() {
+        let std = vec![
+            Red(app(Not, true), false.into()),
+            Eqv(app(Not, app(Not, "x")), "x".into()),
+        ];
+        let a: Expr = (app(Not, true), app(Not, app(Not, false))).into();
+        assert_eq!(a.positions().len(), 9);
+        assert_eq!(a.get(&"1.1".parse().unwrap()), Some(&app(Not, false)));
+        assert_eq!(a.get(&Pos(vec![2])), None);
+        assert_eq!(a.replace(&Pos(vec![0, 1]), false.into()).unwrap(),
+                   (app(Not, false), app(Not, app(Not, false))).into());
+
+        assert_eq!(a.rewrite_at(&std, &Pos(vec![0]), 0, Dir::Forward).unwrap(),
+                   (false, app(Not, app(Not, false))).into());
+        assert_eq!(a.rewrite_at(&std, &Pos(vec![1]), 1, Dir::Forward).unwrap(),
+                   (app(Not, true), false).into());
+        assert_eq!(a.rewrite_at(&std, &Pos(vec![0, 1]), 1, Dir::Backward).unwrap(),
+                   (app(Not, app(Not, app(Not, true))), app(Not, app(Not, false))).into());
+        assert_eq!(a.rewrite_at(&std, &Pos(vec![0]), 0, Dir::Backward), Err(Error::InvalidRule));
+        assert_eq!(a.rewrite_at(&std, &Pos(vec![1]), 0, Dir::Forward), Err(Error::NoReductionRule));
+        assert_eq!(a.rewrite_at(&std, &Pos(vec![3]), 0, Dir::Forward), Err(Error::InvalidPosition));
+    }
+
+    #[test]
+    fn tactics() {
+        let std = &std();
+        let a = parse_str("(len . concat)(a, b)").unwrap();
+        let tactic: Tactic = "repeat reduce; \
+            rewrite \"(f . (g0, g1))(a)(b) <=> f(g0(a)(b))(g1(a)(b))\"; \
+            repeat reduce".parse().unwrap();
+        let trace = tactic.run(&a, std).unwrap();
+        assert_eq!(trace.result(), &app2(Add, app(Len, "a"), app(Len, "b")));
+        if let Justification::Rewrite(_, Dir::Forward, pos) = &trace.steps[3].justification {
+            assert!(pos.is_root());
+        } else {panic!()}
+
+        let tactic: Tactic = "try reduce then first [inline foo, rewrite <- 0 at 1]"
+            .parse().unwrap();
+        let std = vec![Eqv(app(Not, app(Not, "x")), "x".into())];
+        let trace = tactic.run(&app(Not, true), &std).unwrap();
+        assert_eq!(trace.result(), &app(Not, app(Not, app(Not, true))));
+        assert_eq!(trace.steps.len(), 1);
+        assert_eq!(Tactic::Inline(Not).run(&app(Not, true), &std), Err(Error::NoDefinition));
+        assert_eq!(Tactic::Inline("foo".into()).run(&app(Not, true), &std), Err(Error::NoProgress));
+
+        // Repeating stops when the expression returns to an earlier one.
+        let tactic: Tactic = "repeat rewrite <- 0".parse().unwrap();
+        let comm = vec![Eqv(app2(Add, "x", "y"), app2(Add, "y", "x"))];
+        let trace = tactic.run(&app2(Add, "a", "b"), &comm).unwrap();
+        assert!(trace.steps.is_empty());
+        let tactic = Tactic::Repeat(Box::new(
+            Tactic::Rewrite(RuleRef::Index(0), Dir::Backward, Some(Pos::root()))));
+        assert_eq!(tactic.run(&app(Not, true), &std), Err(Error::NoProgress));
+    }
+
+    #[test]
+    fn egraph() {
+        let std = &std();
+        let mut rev = std.clone();
+        rev.reverse();
+        for knowledge in [std, &rev] {
+            let mut g = EGraph::new();
+            let a = g.add_expr(&path(And, Not));
+            assert!(g.saturate(knowledge, 1000));
+            assert_eq!(g.extract(a, &Size), (1.0, Or.into()));
+        }
+
+        let knowledge = vec![Eqv(app(Not, app(Not, "x")), "x".into())];
+        let mut g = EGraph::new();
+        let a = g.add_expr(&app(Not, app(Not, app(Not, true))));
+        assert!(g.saturate(&knowledge, 100));
+        assert_eq!(g.extract(a, &Size).1, app(Not, true));
+
+        let knowledge = vec![Red(app(Not, "x"), app(Not, app(Id, "x")))];
+        let mut g = EGraph::new();
+        let a = g.add_expr(&app(Not, true));
+        assert!(!g.saturate(&knowledge, 20));
+        assert_eq!(g.extract(a, &Size).1, app(Not, true));
+
+        let knowledge = vec![Eqv(path(And, Not), Or.into())];
+        let mut g = EGraph::new();
+        let a = g.add_expr(&Or.into());
+        g.saturate(&knowledge, 100);
+        let no_or = |node: &ENode, children: &[f64]| {
+            let c = if let ENode::Sym(Or) = node {10.0} else {1.0};
+            c + children.iter().sum::<f64>()
+        };
+        assert_eq!(g.extract(a, &no_or).1, path(And, Not));
+
+        // Equal values are shared, and so is NaN.
+        let mut g = EGraph::new();
+        assert_eq!(g.add_expr(&0.0.into()), g.add_expr(&(-0.0).into()));
+        assert_eq!(g.add_expr(&f64::NAN.into()), g.add_expr(&(-f64::NAN).into()));
+        assert_ne!(g.add_expr(&0.0.into()), g.add_expr(&f64::NAN.into()));
+    }
+
+    #[test]
+    fn cost_simplify() {
+        let std = &std();
+        let a = parse_str("(len . concat)(a, b)").unwrap();
+        assert_eq!(a.cost(&Size), 7.0);
+
+        let model = CostModel::new().symbol(Concat, 10.0);
+        let (c, trace) = simplify(&a, std, &model);
+        assert_eq!(trace.result(), &app2(Add, app(Len, "a"), app(Len, "b")));
+        assert_eq!(c, 9.0);
+        assert_eq!(trace.steps.len(), 3);
+
+        let (c, trace) = simplify(&a, std, &CostModel::new());
+        assert_eq!(c, 7.0);
+        assert!(trace.steps.is_empty());
+    }
+
+    #[test]
+    fn ac_matching() {
+        let std = &std();
+        let ac = ac_symbols(std);
+        assert!(ac.contains(&Add));
+        assert!(ac.contains(&Mul));
+        assert!(!ac.contains(&Sub));
+
+        let rules = &[
+            commutative(Add),
+            associative(Add),
+            Red(app2(Add, "x", app(Neg, "x")), 0.0.into()),
+        ];
+        let a = app2(Add, "y", app2(Add, app(Neg, "z"), "z"));
+        let (pattern, value) = (app2(Add, "x", app(Neg, "x")), app2(Add, app(Neg, "z"), "z"));
+        assert!(Context::with_ac(&ac_symbols(rules)).bind(&pattern, &value));
+        // Contexts without AC symbols match syntactically.
+        assert!(!Context::new().bind(&pattern, &value));
+        assert_eq!(a.reduce(rules).unwrap().0, app2(Add, "y", 0.0));
+
+        // Every operand is matched, so leftover operands need a variable in the pattern.
+        let compute = Red(app2(Add, ret_var("x"), ret_var("y")), binop_ret_var("x", "y", Add));
+        let a = app2(Add, app2(Add, 1.0, "a"), 2.0);
+        let rules = &[commutative(Add), associative(Add), compute];
+        assert_eq!(a.reduce(rules), Err(Error::NoReductionRule));
+        let compute = Red(app2(Add, ret_var("x"), app2(Add, ret_var("y"), "z")),
+                          app2(Add, binop_ret_var("x", "y", Add), "z"));
+        let rules = &[commutative(Add), associative(Add), compute];
+        assert_eq!(a.reduce(rules).unwrap().0, app2(Add, 3.0, "a"));
+    }
+
+    #[test]
+    fn higher_order_patterns() {
+        // `(x, y, $F(x)) => $F(y)`
+        let rules = &[Red(("x", "y", ho_var("F", &["x"])).into(), ho_var("F", &["y"]))];
+        assert_eq!(format!("{}", rules[0]), "(x, y, $F(x)) => $F(y)");
+        assert_eq!(parse_knowledge_str("(x, y, $F(x)) => $F(y)").unwrap(), rules);
+        assert_eq!(parse_str("$F(x, y)(z)").unwrap(), app(ho_var("F", &["x", "y"]), "z"));
+        let a: Expr = ("a", "b", app2(Add, "a", app(Neg, "a"))).into();
+        assert_eq!(a.reduce(rules).unwrap().0, app2(Add, "b", app(Neg, "b")));
+
+        // The arguments are bound first, regardless of the order in the pattern.
+        for (pattern, value) in [
+            ((ho_var("F", &["x"]), "x").into(), (app(Len, "a"), "a").into()),
+            (("x", ho_var("F", &["x"])).into(), ("a", app(Len, "a")).into()),
+        ] {
+            let mut ctx = Context::new();
+            assert!(ctx.bind(&pattern, &value));
+            let f = ctx.vars.iter().find(|(name, _)| **name == "F").unwrap();
+            assert_eq!(f.1, app(Len, Hole(0)));
+        }
+        let rules = &[Red((ho_var("F", &["x"]), "x", "y").into(), ho_var("F", &["y"]))];
+        let a: Expr = (app(Len, "a"), "a", "b").into();
+        assert_eq!(a.reduce(rules).unwrap().0, app(Len, "b"));
+
+        // Higher-order pattern variables must be applied on the right side.
+        let rules = parse_knowledge_str("(x, $F(x)) => F").unwrap();
+        let a = parse_str("(a, and(a)(b))").unwrap();
+        assert_eq!(a.reduce(&rules), Err(Error::UnappliedHoVar(Arc::new("F".into()))));
+        let a: Expr = (app(Len, "a"), "a", "b").into();
+
+        // Arguments must be bound elsewhere in the pattern.
+        let rules = &[Red(ho_var("F", &["x"]), "x".into())];
+        assert_eq!(a.reduce(rules), Err(Error::NoReductionRule));
+        assert_eq!(a.why_not(rules, 0).unwrap().mismatch, Mismatch::Unbound(Arc::new("x".into())));
+    }
+
+    #[test]
+    fn conditional_rules() {
+        let mut rules = parse_knowledge_str(r#"div(x)(x) => \1 when not(eq(x, \0))"#).unwrap();
+        let cond = app(Not, app(Eq, ("x", 0.0)));
+        assert_eq!(rules[0], CondRed(app2(Div, "x", "x"), 1.0.into(), cond));
+        assert_eq!(format!("{}", rules[0]), "div(x)(x) => \\1 when not(eq(x, \\0))");
+        rules.extend(std());
+        let rules = &rules;
+
+        let a = app2(Div, 2.0, 2.0);
+        assert_eq!(a.reduce(rules).unwrap(), (1.0.into(), 0));
+        let trace = Tactic::Reduce.run(&a, rules).unwrap();
+        assert_eq!(trace.steps[0].condition, Some(app(Not, app(Eq, (2.0, 0.0)))));
+
+        let b = app2(Div, "b", "b");
+        assert_ne!(b.reduce_root(rules).map(|(_, i)| i), Ok(0));
+        assert_eq!(b.why_not(rules, 0).unwrap().mismatch,
+                   Mismatch::Condition(app(Not, app(Eq, ("b", 0.0)))));
+
+        // Conditions can be discharged by a user predicate.
+        let nonzero = |c: &Expr| c == &app(Not, app(Eq, ("b", 0.0)));
+        assert_eq!(b.reduce_with_discharge(rules, &nonzero), Ok((1.0.into(), 0)));
+        assert_eq!(b.reduce_all_with_discharge(rules, &nonzero), 1.0.into());
+        assert_ne!(b.reduce_root(rules).map(|(_, i)| i), Ok(0));
+
+        // A condition that triggers its own rule is stopped.
+        let rules = parse_knowledge_str(r#"f(x) => \1 when eq(f(x), \1)"#).unwrap();
+        assert_eq!(app("f", "a").reduce(&rules), Err(Error::ConditionTooDeep));
+        assert_eq!(app("f", "a").reduce_all(&rules), app("f", "a"));
+    }
+
+    #[test]
+    fn rule_metadata() {
+        let rules = parse_rules_str("// Booleans.\nand[not] => or\n\nnot . not => idb", "bool.txt")
+            .unwrap();
+        assert_eq!(rules[1].meta.source, Some((Arc::new("bool.txt".into()), 4)));
+        let rules = vec![
+            rules[0].clone().name("and-not").tag("boolean"),
+            rules[1].clone().name("not-not").tag("boolean").priority(1),
+            Rule::new(Red(comp("x", Id), "x".into())).name("comp-id"),
+        ];
+        assert_eq!(format!("{}", rules[0]), "and-not: and[not] => or");
+
+        let boolean = select(&rules, |r| r.meta.has_tag("boolean"));
+        assert_eq!(boolean, vec![rules[1].knowledge.clone(), rules[0].knowledge.clone()]);
+        let a = comp(Not, Not);
+        assert_eq!(a.reduce(&select(&rules, |r| !r.meta.is_named("not-not"))),
+                   Err(Error::NoReductionRule));
+    }
+
+    #[test]
+    fn theories() {
+        use theory::Theory;
+
+        assert_eq!(theory::with_dependencies(&[Theory::Lists]),
+                   vec![Theory::CoreStructural, Theory::Boolean, Theory::Arith, Theory::Lists]);
+        let std = std();
+        let n: usize = Theory::ALL.iter().map(|t| t.knowledge().len()).sum();
+        assert_eq!(std.len(), n);
+
+        let boolean = theory::compose(&[Theory::Boolean]);
+        assert!(boolean.contains(&Red(path(And, Not), Or.into())));
+        assert!(boolean.contains(&Red(comp("x", Id), "x".into())));
+        assert!(!boolean.iter().any(|k| theory::arith().contains(k)));
+        let a = parse_str("and[not]").unwrap();
+        assert_eq!(a.eval(&boolean), a.eval(&std));
+
+        let rules = theory::rules(&Theory::ALL);
+        let dot = |r: &Rule|
+            matches!(&r.knowledge, Red(a, _) if format!("{}", a).starts_with("dot"));
+        assert!(rules.iter().any(|r| dot(r) && r.meta.has_tag("lists")));
+
+        for (name, _) in theory::NAMES {
+            assert_eq!(rules.iter().filter(|r| r.meta.is_named(name)).count(), 1, "{}", name);
+        }
+        let a = parse_str("dot([a, b])([c, d])").unwrap();
+        let expanded = parse_str("add(mul(a)(c))(mul(b)(d))").unwrap();
+        let i = rules.iter().position(|r| r.meta.is_named("dot-product")).unwrap();
+        assert_eq!(a.reduce(&std), Ok((expanded.clone(), i)));
+        let without = select(&rules, |r| !r.meta.is_named("dot-product"));
+        assert_ne!(a.reduce_all(&without), expanded);
+    }
+
+    #[test]
+    fn lints() {
+        let k = parse_knowledge_str(r"
+            foo := and
+            foo := or
+            not(x) => and(y)
+            sin(x) => x
+            sin(\1) => \0
+            sin(x) => x
+            sqrt(x) <=> pow(x)(y)
+            cos(x) => cos(x)
+        ").unwrap();
+        assert_eq!(lint(&k), vec![
+            Lint::DuplicateDef(1, 0),
+            Lint::UnboundVar(2, Arc::new("y".into())),
+            Lint::Shadowed(4, 3),
+            Lint::Duplicate(5, 3),
+            Lint::OneWayEqv(6),
+            Lint::Identity(7),
+        ]);
+        assert_eq!(format!("{}", lint(&k)[2]), "rule 4: never applies, because of rule 3");
+    }
+
+    #[test]
+    fn redexes() {
+        let std = std();
+        let a = parse_str("(not · not)(idb(x))").unwrap();
+        let redexes: Vec<Redex> = a.redexes(&std).collect();
+        let pos: Vec<String> = redexes.iter().map(|r| format!("{}", r.pos)).collect();
+        assert_eq!(pos, vec!["0", "1.0"]);
+        for r in &redexes {
+            assert_eq!(a.rewrite_at(&std, &r.pos, r.rule, Dir::Forward), Ok(r.expr.clone()));
+        }
+        assert_eq!(a.reduce(&std), Ok((redexes[0].expr.clone(), redexes[0].rule)));
+
+        let b = parse_str("and(x, y)").unwrap();
+        let r = b.redexes(&std).next().unwrap();
+        let var = |x: &str| Arc::new(String::from(x));
+        assert_eq!(r.bindings,
+                   vec![(var("x"), And.into()), (var("y"), "x".into()), (var("z"), "y".into())]);
+    }
+
+    #[test]
+    fn queries() {
+        let std = std();
+        let var = |x: &str| Arc::new(String::from(x));
+        let (a, b) = parse_query_str("and[not] = ?h").unwrap();
+        assert_eq!(format!("{}", b), "?h");
+        let solutions = solve(&a, &b, &std, SOLVE_LIMIT);
+        assert_eq!(solutions[0], vec![(var("h"), Or.into())]);
+        assert!(solutions.contains(&vec![(var("h"), a.clone())]));
+
+        let (a, b) = parse_query_str("?f[not] = or").unwrap();
+        assert_eq!(solve(&a, &b, &std, SOLVE_LIMIT), vec![vec![(var("f"), And.into())]]);
+
+        let mut ctx = Context::new();
+        let x: Expr = MetaVar(var("x")).into();
+        assert!(!ctx.unify(&x, &app(Not, x.clone())));
+        assert!(ctx.unify(&app(Not, x.clone()), &app(MetaVar(var("y")), true)));
+        assert_eq!(ctx.substitute_metas(&app(x, MetaVar(var("y")))), app(true, Not));
+    }
+
+    #[test]
+    fn unification() {
+        let var = |x: &str| Arc::new(String::from(x));
+        let a = parse_str("add(x)(not(y))").unwrap();
+        let b = parse_str("add(and(z))(z)").unwrap();
+        let subst = unify(&a, &b).unwrap();
+        assert_eq!(subst, vec![
+            (var("x"), parse_str("and(not(y))").unwrap()),
+            (var("z"), parse_str("not(y)").unwrap()),
+        ]);
+        let ctx = Context {vars: subst, ac: vec![]};
+        assert_eq!(ctx.substitute_unified(&a), ctx.substitute_unified(&b));
+
+        assert_eq!(unify(&"x".into(), &app(Not, "x")), None);
+        assert_eq!(unify(&app("f", "x"), &app(Not, "y")),
+                   Some(vec![(var("f"), Not.into()), (var("x"), "y".into())]));
+        assert_eq!(unify(&app(Not, Any), &app(Not, "x")), Some(vec![]));
+    }
+
+    #[test]
+    fn boolean_paths() {
+        let mut std = std();
+        std.extend(parse_knowledge_str("k := if(if(false, true), false)").unwrap());
+        assert_eq!(truth_table(&Imply.into(), 2, &std), Ok(vec![true, true, false, true]));
+        assert_eq!(truth_table(&"k".into(), 2, &std), truth_table(&Exc.into(), 2, &std));
+
+        let nots = [Not.into(), Not.into()];
+        assert_eq!(boolean_path(&Nand.into(), &nots, &Id.into(), &std), Ok(Or.into()));
+        assert_eq!(boolean_path(&"k".into(), &nots, &Not.into(), &std),
+                   Ok(_if(true, _if(false, true))));
+        assert_eq!(boolean_path(&And.into(), &[True1.into(), Id.into()], &Id.into(), &std),
+                   Err(Error::NoPath));
+
+        let rules = boolean_path_rules(&[And.into(), Xor.into()], 2, &[Not.into()], &std);
+        assert_eq!(rules, vec![Red(path(And, Not), Or.into()), Red(path(Xor, Not), Eqb.into())]);
+        let rules = boolean_path_rules(&[Not.into()], 1, &[Not.into(), Idb.into()], &std);
+        assert!(rules.contains(&Red(path(Not, (Idb, Not)), Idb.into())));
+    }
+
+    #[test]
+    fn existential_paths() {
+        let mut std = std();
+        let ex = |s: &str| app(Ex, parse_str(s).unwrap());
+        assert_eq!(ex("not . and").reduce(&std).unwrap().0, True1.into());
+        assert_eq!(ex("and . (fst, not . fst)").reduce(&std).unwrap().0, Not.into());
+        assert_eq!(ex("xor{eqb}").reduce(&std).unwrap().0, Not.into());
+
+        std.extend(parse_knowledge_str("k := if(true1, true1)").unwrap());
+        assert_eq!(existential_path(&"k".into(), &std), Ok(Idb.into()));
+        assert_eq!(ex("k").reduce(&std).unwrap().0, Idb.into());
+        for f in ["add", "x", "sqrt", "f . g"] {
+            assert_eq!(ex(f).reduce(&std), Err(Error::NoReductionRule), "{}", f);
+        }
+        let rule = std.iter().position(|k| matches!(k, Red(_, Sym(UnopRetVar(_, f))) if **f == Ex))
+            .unwrap();
+        assert_eq!(ex("add").rewrite_at(&std, &Pos::root(), rule, Dir::Forward),
+                   Err(Error::NotApplicable));
+        assert_eq!(ex("k").rewrite_at(&std, &Pos::root(), rule, Dir::Forward), Ok(Idb.into()));
+
+        // Cyclic definitions are not evaluated.
+        std.extend(parse_knowledge_str("foo := not . foo").unwrap());
+        assert_eq!(existential_path(&"foo".into(), &std), Err(Error::CyclicDefinition));
+        assert_eq!(ex("foo").reduce(&std), Err(Error::NoReductionRule));
+        assert_eq!(existential_path(&parse_str("(not, idb)").unwrap(), &std), Ok(Xor.into()));
+        assert_eq!(existential_path(&Add.into(), &std), Err(Error::NoDefinition));
+        assert_eq!(trivial_path(&parse_str("not . and{eqb}").unwrap(), &std), Ok(Eqb.into()));
+        assert_eq!(trivial_path(&"k".into(), &std), Ok(true.into()));
+    }
+
+    #[test]
+    fn truth_table_synthesis() {
+        let std = std();
+        for f in BOOLEAN_BINOPS.iter().take(10) {
+            let table = truth_table(&f.clone().into(), 2, &std).unwrap();
+            assert!(std.contains(&boolean_def(f.clone(), &table)));
+        }
+        let table = [false, false, false, true, false, true, true, true];
+        let maj = _if(_if(true, _if(true, false)), _if(_if(true, false), false));
+        assert_eq!(boolean_def("maj".into(), &table), Def("maj".into(), maj.clone()));
+        assert_eq!(truth_table(&maj, 3, &std), Ok(table.into()));
+
+        assert_eq!(boolean_symbol(&_if(_if(false, true), true), &std), Some(Nand));
+        assert_eq!(boolean_symbol(&parse_str("not . or").unwrap(), &std), Some(Nor));
+        assert_eq!(boolean_symbol(&maj, &std), None);
+        let a = app(Not, Nand).inline_all(&std).unwrap();
+        let (a, def) = a.fold_boolean(&std).unwrap();
+        assert_eq!(def, Def(Not, _if(false, true)));
+        let (a, def) = a.fold_boolean(&std).unwrap();
+        assert_eq!(def, Def(Nand, _if(_if(false, true), true)));
+        assert_eq!(a, app(Not, Nand));
+        assert_eq!(a.fold_boolean(&std), None);
+    }
+
+    #[test]
+    fn decide() {
+        let std = std();
+        let eqv = |a: &str, b: &str| {
+            boolean_eqv(&parse_str(a).unwrap(), &parse_str(b).unwrap(), &std)
+        };
+        assert_eq!(eqv("not . and", "nand"), Ok(true));
+        assert_eq!(eqv("and[not]", "or"), Ok(true));
+        assert_eq!(eqv("imply . (not . fst, not . snd)", "if(true, if(false, true))"), Ok(true));
+        assert_eq!(eqv("xor[not x not -> id]", "not . (not . xor)"), Ok(true));
+        assert_eq!(eqv("and{eqb}", "fstb{eqb}"), Ok(true));
+        assert_eq!(eqv("and{eqb}", "fstb"), Ok(false));
+        assert_eq!(eqv("and", "or"), Ok(false));
+        assert_eq!(eqv("and", "x"), Err(Error::NoDefinition));
+        for (a, b) in [("idb", "fstb"), ("idb", "fst"), ("true1", "true2"),
+                       ("false1", "false2"), ("not", "not . fstb")] {
+            assert_eq!(eqv(a, b), Err(Error::ArityMismatch), "{} {}", a, b);
+        }
+        assert_eq!(eqv("(idb, fst)", "(idb, fst)"), Err(Error::ArityMismatch));
+        assert_eq!(eqv("idb . (not, idb)", "not"), Err(Error::InvalidComputation));
+        let a = "if(if(and, or), false)";
+        assert_eq!(eqv(a, a), Err(Error::TooManyArguments));
+        assert_eq!(boolean_arity(&parse_str("if(and, false)").unwrap(), &std), Ok(3));
+
+        let tactic: Tactic = "inline and; decide".parse().unwrap();
+        let mut trace = tactic.run(&path(And, Not), &std).unwrap();
+        assert_eq!(trace.steps[1].justification, Justification::Decide);
+        assert_eq!(trace.result(), &boolean_normal_form(&Or.into(), &std).unwrap());
+        assert_eq!(trace.check(&std), Ok(()));
+        trace.steps[1].expr = Nand.into();
+        assert_eq!(trace.check(&std), Err(Error::InvalidStep(1)));
+        let trace = Trace {start: Idb.into(), steps: vec![Step {
+            expr: Fstb.into(),
+            justification: Justification::Decide,
+            condition: None,
+        }]};
+        assert_eq!(trace.check(&std), Err(Error::InvalidStep(0)));
+
+        assert_eq!(solve(&comp(Not, And), &Nand.into(), &std, SOLVE_LIMIT), vec![vec![]]);
+        assert!(solve(&comp(Not, And), &Or.into(), &std, SOLVE_LIMIT).is_empty());
+        assert!(solve(&Idb.into(), &Fstb.into(), &std, SOLVE_LIMIT).is_empty());
+
+        // Cyclic definitions are not decided, but searched.
+        let mut std = std;
+        std.extend(parse_knowledge_str("foo := not . foo").unwrap());
+        let foo: Expr = "foo".into();
+        assert_eq!(boolean_eqv(&foo, &Not.into(), &std), Err(Error::CyclicDefinition));
+        assert!(solve(&foo, &Not.into(), &std, SOLVE_LIMIT).is_empty());
+        let tactic: Tactic = "decide".parse().unwrap();
+        assert_eq!(tactic.run(&foo, &std).map(|t| t.steps), Err(Error::CyclicDefinition));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn encoding() {
+        let a = parse_str("(len · concat)(a, [\\1, b..])[not ⨯ id → id]").unwrap();
+        let json = to_json(&a).unwrap();
+        assert!(json.starts_with(r#"{"version":1,"data":{"Op":["Path""#));
+        assert_eq!(from_json::<Expr>(&json), Ok(a.clone()));
+        assert_eq!(from_binary::<Expr>(&to_binary(&a).unwrap()), Ok(a.clone()));
+        assert_eq!(to_json(&Sym(Var(Arc::new("x".into())))).unwrap(),
+                   r#"{"version":1,"data":{"Sym":{"Var":"x"}}}"#);
+
+        let std = std();
+        assert_eq!(from_json::<Vec<Knowledge>>(&to_json(&std).unwrap()).unwrap(), std);
+        assert_eq!(from_binary::<Vec<Knowledge>>(&to_binary(&std).unwrap()).unwrap(), std);
+        assert!(from_json::<Expr>(r#"{"version":0,"data":{"Sym":"Not"}}"#).is_err());
+    }
 }
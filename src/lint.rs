@@ -0,0 +1,159 @@
+use std::fmt;
+
+use super::*;
+
+/// Describes a likely mistake in a knowledge base.
+///
+/// Rules are referred to by their index in the knowledge base.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Lint {
+    /// The right side or the condition uses a variable that the left side does not bind.
+    ///
+    /// Stores the rule and the variable.
+    UnboundVar(usize, Arc<String>),
+    /// The rule never applies, because an earlier reduction matches everything it matches.
+    ///
+    /// Stores the rule and the earlier rule.
+    Shadowed(usize, usize),
+    /// The rule is the same as an earlier rule.
+    ///
+    /// Stores the rule and the earlier rule.
+    Duplicate(usize, usize),
+    /// An equivalence that can only be used in one direction,
+    /// because one side does not bind all variables of the other side.
+    OneWayEqv(usize),
+    /// A symbol is defined again.
+    ///
+    /// Stores the definition and the earlier definition.
+    DuplicateDef(usize, usize),
+    /// A rule that rewrites an expression to itself.
+    Identity(usize),
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, w: &mut fmt::Formatter<'_>) -> std::result::Result<(), fmt::Error> {
+        use Lint::*;
+
+        match self {
+            UnboundVar(i, a) => write!(w, "rule {}: `{}` is not bound by the left side", i, a)?,
+            Shadowed(i, j) => write!(w, "rule {}: never applies, because of rule {}", i, j)?,
+            Duplicate(i, j) => write!(w, "rule {}: same as rule {}", i, j)?,
+            OneWayEqv(i) => write!(w, "rule {}: equivalence only works in one direction", i)?,
+            DuplicateDef(i, j) => write!(w, "rule {}: symbol is already defined by rule {}", i, j)?,
+            Identity(i) => write!(w, "rule {}: rewrites to itself", i)?,
+        }
+        Ok(())
+    }
+}
+
+/// Checks a knowledge base for likely mistakes.
+pub fn lint(knowledge: &[Knowledge]) -> Vec<Lint> {
+    use Lint::*;
+
+    let mut ctx = Context::with_ac(&ac_symbols(knowledge));
+    let mut res = vec![];
+    for (i, k) in knowledge.iter().enumerate() {
+        let earlier = &knowledge[..i];
+        if let Some(j) = earlier.iter().position(|e| same_rule(e, k)) {
+            res.push(Duplicate(i, j));
+            continue;
+        }
+        match k {
+            Def(a, _) => {
+                if let Some(j) = earlier.iter().position(|e| matches!(e, Def(b, _) if a == b)) {
+                    res.push(DuplicateDef(i, j));
+                }
+            }
+            Red(a, b) | CondRed(a, b, _) => {
+                if a == b {res.push(Identity(i))};
+                let cond = if let CondRed(_, _, c) = k {Some(c)} else {None};
+                res.extend(unbound_vars(a, b, cond).into_iter().map(|x| UnboundVar(i, x)));
+                if let Some(j) = earlier.iter().position(|e| match e {
+                    Red(b, _) if !checks_constraints(b) => ctx.bind(b, a),
+                    _ => false,
+                }) {
+                    ctx.vars.clear();
+                    res.push(Shadowed(i, j));
+                }
+            }
+            Eqv(a, b) => {
+                if a == b {res.push(Identity(i))};
+                let forward = unbound_vars(a, b, None);
+                let backward = unbound_vars(b, a, None);
+                match (forward.is_empty(), backward.is_empty()) {
+                    (true, true) => {}
+                    (true, false) | (false, true) => res.push(OneWayEqv(i)),
+                    (false, false) => res.extend(forward.into_iter().map(|x| UnboundVar(i, x))),
+                }
+            }
+        }
+    }
+    res
+}
+
+fn same_rule(a: &Knowledge, b: &Knowledge) -> bool {
+    match (a, b) {
+        (Eqv(a0, a1), Eqv(b0, b1)) => (a0 == b0 && a1 == b1) || (a0 == b1 && a1 == b0),
+        _ => a == b,
+    }
+}
+
+/// Returns `true` if a pattern checks domain constraints,
+/// which can not be decided when matching against another pattern.
+fn checks_constraints(a: &Expr) -> bool {
+    match a {
+        Sym(NoConstrVar(_)) => true,
+        Sym(HeadTailTup(a, b)) | Sym(HeadTailList(a, b)) | Op(_, a, b) =>
+            checks_constraints(a) || checks_constraints(b),
+        Tup(items) | List(items) => items.iter().any(checks_constraints),
+        Sym(_) | Ret(_) => false,
+    }
+}
+
+/// Returns the variables used by `b` or a condition that are not bound by the pattern `a`.
+fn unbound_vars(a: &Expr, b: &Expr, cond: Option<&Expr>) -> Vec<Arc<String>> {
+    let mut bound = vec![];
+    bound_vars(a, &mut bound);
+    let mut used = vec![];
+    used_vars(b, &mut used);
+    if let Some(c) = cond {used_vars(c, &mut used)};
+    used.retain(|x| !bound.contains(x));
+    used
+}
+
+/// Collects the variables that a pattern binds.
+fn bound_vars(a: &Expr, res: &mut Vec<Arc<String>>) {
+    match a {
+        Sym(Var(x)) | Sym(NoConstrVar(x)) | Sym(RetVar(x)) |
+        Sym(Singleton(x)) | Sym(ListVar(x)) | Sym(HoVar(x, _)) => res.push(x.clone()),
+        Sym(HeadTailTup(a, b)) | Sym(HeadTailList(a, b)) | Op(_, a, b) => {
+            bound_vars(a, res);
+            bound_vars(b, res);
+        }
+        Tup(items) | List(items) => for a in items {bound_vars(a, res)},
+        Sym(_) | Ret(_) => {}
+    }
+}
+
+/// Collects the variables that substitution looks up.
+fn used_vars(a: &Expr, res: &mut Vec<Arc<String>>) {
+    let mut push = |x: &Arc<String>| if !res.contains(x) {res.push(x.clone())};
+    match a {
+        Sym(Var(x)) | Sym(UnopRetVar(x, _)) => push(x),
+        Sym(BinopRetVar(x, y, _)) => {
+            push(x);
+            push(y);
+        }
+        Sym(HoVar(f, args)) => {
+            push(f);
+            for x in args {push(x)}
+        }
+        Op(_, a, b) => {
+            used_vars(a, res);
+            used_vars(b, res);
+        }
+        Tup(items) | List(items) => for a in items {used_vars(a, res)},
+        Sym(_) | Ret(_) => {}
+    }
+}
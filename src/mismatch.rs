@@ -0,0 +1,162 @@
+use std::fmt;
+
+use super::*;
+
+/// Describes why a pattern did not match an expression.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Mismatch {
+    /// The expression differs from the pattern, e.g. by operator or symbol.
+    ///
+    /// Stores the pattern and the expression.
+    Differ(Expr, Expr),
+    /// A tuple or list has a different length than the pattern.
+    ///
+    /// Stores the pattern and the expression.
+    Length(Expr, Expr),
+    /// A function without domain constraints was matched against
+    /// an expression that has constraints.
+    HasConstraint(Arc<String>, Expr),
+    /// A repeated variable was bound to two different expressions.
+    ///
+    /// Stores the variable, the first binding and the second binding.
+    Conflict(Arc<String>, Expr, Expr),
+    /// An argument of a higher-order pattern variable is not bound elsewhere in the pattern.
+    Unbound(Arc<String>),
+    /// The pattern matched, but the right side could not be computed.
+    Substitute(Error),
+    /// The pattern matched, but the condition does not hold.
+    ///
+    /// Stores the condition after substitution.
+    Condition(Expr),
+    /// The knowledge is not a reduction or an equivalence.
+    NotRule,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, w: &mut fmt::Formatter<'_>) -> std::result::Result<(), fmt::Error> {
+        use Mismatch::*;
+
+        match self {
+            Differ(a, b) => write!(w, "`{}` does not match `{}`", b, a)?,
+            Length(a, b) => write!(w, "`{}` does not have the same length as `{}`", b, a)?,
+            HasConstraint(a, b) =>
+                write!(w, "`{}` has domain constraints, which `{}` does not allow", b, a)?,
+            Conflict(a, b, c) => write!(w, "`{}` is bound to `{}` and to `{}`", a, b, c)?,
+            Unbound(a) => write!(w, "`{}` is not bound by the pattern", a)?,
+            Substitute(err) => write!(w, "the pattern matched, but substitution failed: {:?}", err)?,
+            Condition(a) => write!(w, "the pattern matched, but `{}` does not hold", a)?,
+            NotRule => write!(w, "not a reduction or an equivalence")?,
+        }
+        Ok(())
+    }
+}
+
+/// Reports the first mismatch when trying to apply a rule.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WhyNot {
+    /// The position of the mismatch in the expression.
+    pub pos: Pos,
+    /// The mismatch.
+    pub mismatch: Mismatch,
+}
+
+impl fmt::Display for WhyNot {
+    fn fmt(&self, w: &mut fmt::Formatter<'_>) -> std::result::Result<(), fmt::Error> {
+        write!(w, "at {}: {}", self.pos, self.mismatch)
+    }
+}
+
+impl Expr {
+    /// Explains why a rule in a knowledge base does not apply to the expression.
+    ///
+    /// Returns `None` if the rule applies.
+    /// For equivalences, the left side is reported when neither side matches.
+    pub fn why_not(&self, knowledge: &[Knowledge], rule: usize) -> Option<WhyNot> {
+        let mut ctx = Context::with_ac(&ac_symbols(knowledge));
+        let root = |mismatch| Some(WhyNot {pos: Pos::root(), mismatch});
+        match knowledge.get(rule) {
+            Some(Red(a, b)) => match ctx.bind_or_mismatch(a, self) {
+                Ok(()) => match ctx.substitute_in(b, knowledge) {
+                    Ok(_) => None,
+                    Err(err) => root(Mismatch::Substitute(err)),
+                },
+                Err(why_not) => Some(why_not),
+            },
+            Some(CondRed(a, b, c)) => match ctx.bind_or_mismatch(a, self) {
+                Ok(()) => match (ctx.condition(c, knowledge),
+                                 ctx.substitute_in(b, knowledge)) {
+                    (Ok(_), Ok(_)) => None,
+                    (Ok(_), Err(err)) => root(Mismatch::Substitute(err)),
+                    (Err(_), _) => root(Mismatch::Condition(
+                        ctx.substitute(c).unwrap_or_else(|_| c.clone()))),
+                },
+                Err(why_not) => Some(why_not),
+            },
+            Some(Eqv(a, b)) => match ctx.bind_or_mismatch(a, self) {
+                Ok(()) => None,
+                Err(why_not) => if ctx.bind(b, self) {None} else {Some(why_not)},
+            },
+            _ => root(Mismatch::NotRule),
+        }
+    }
+}
+
+/// Stores a mismatch by reference while binding,
+/// to avoid cloning expressions when a rule does not match.
+pub(crate) struct Fail<'a> {
+    /// Child indices in reverse order.
+    rev_pos: Vec<usize>,
+    kind: FailKind<'a>,
+}
+
+pub(crate) enum FailKind<'a> {
+    Differ(&'a Expr, &'a Expr),
+    Length(&'a Expr, &'a Expr),
+    HasConstraint(&'a Arc<String>, &'a Expr),
+    /// Stores the index of the first binding in the context.
+    Conflict(&'a Arc<String>, usize, &'a Expr),
+    Unbound(&'a Arc<String>),
+    Owned(Mismatch),
+}
+
+impl<'a> Fail<'a> {
+    pub(crate) fn new(kind: FailKind<'a>) -> Fail<'a> {
+        Fail {rev_pos: vec![], kind}
+    }
+
+    /// Puts the mismatch inside a child with some index.
+    pub(crate) fn at(mut self, i: usize) -> Fail<'a> {
+        self.rev_pos.push(i);
+        self
+    }
+
+    /// Stores the mismatch by value, before the context is changed.
+    pub(crate) fn into_owned(self, ctx: &Context) -> Fail<'a> {
+        let rev_pos = self.rev_pos.clone();
+        Fail {rev_pos, kind: FailKind::Owned(self.why_not(ctx).mismatch)}
+    }
+
+    /// Returns `true` if an argument of a higher-order pattern variable was not bound yet.
+    pub(crate) fn is_unbound(&self) -> bool {
+        matches!(self.kind, FailKind::Unbound(_) | FailKind::Owned(Mismatch::Unbound(_)))
+    }
+
+    /// Converts to a mismatch report, before the context is cleared.
+    pub(crate) fn why_not(self, ctx: &Context) -> WhyNot {
+        use self::FailKind::*;
+
+        let mut pos = self.rev_pos;
+        pos.reverse();
+        let mismatch = match self.kind {
+            Differ(a, b) => Mismatch::Differ(a.clone(), b.clone()),
+            Length(a, b) => Mismatch::Length(a.clone(), b.clone()),
+            HasConstraint(a, b) => Mismatch::HasConstraint(a.clone(), b.clone()),
+            Conflict(a, i, c) => Mismatch::Conflict(a.clone(), ctx.vars[i].1.clone(), c.clone()),
+            Unbound(a) => Mismatch::Unbound(a.clone()),
+            Owned(mismatch) => mismatch,
+        };
+        WhyNot {pos: Pos(pos), mismatch}
+    }
+}
@@ -2,6 +2,93 @@ use super::*;
 
 use piston_meta::{Convert, Range};
 
+/// Names of built-in symbols recognized by the parser.
+///
+/// Names that are not in this table are parsed as variables.
+pub const SYMBOLS: &[(&str, Symbol)] = &[
+    ("triv", Triv),
+    ("∀", Triv),
+    ("dom", Triv),
+    ("ex", Ex),
+    ("∃", Ex),
+    ("codom", Ex),
+    ("false1", False1),
+    ("idb", Idb),
+    ("not", Not),
+    ("true1", True1),
+    ("false2", False2),
+    ("true2", True2),
+    ("and", And),
+    ("or", Or),
+    ("eqb", Eqb),
+    ("xor", Xor),
+    ("nand", Nand),
+    ("nor", Nor),
+    ("exc", Exc),
+    ("imply", Imply),
+    ("fstb", Fstb),
+    ("sndb", Sndb),
+    ("neqb", Xor),
+    ("id", Id),
+    ("lt", Lt),
+    ("le", Le),
+    ("gt", Gt),
+    ("ge", Ge),
+    ("mul", Mul),
+    ("div", Div),
+    ("rem", Rem),
+    ("pow", Pow),
+    ("rpow", Rpow),
+    ("sqrt", Sqrt),
+    ("even", Even),
+    ("odd", Odd),
+    ("neg", Neg),
+    ("add", Add),
+    ("sub", Sub),
+    ("len", Len),
+    ("concat", Concat),
+    ("sum", Sum),
+    ("mul_mat", MulMat),
+    ("det", Det),
+    ("dim", Dim),
+    ("fst", Fst),
+    ("snd", Snd),
+    ("ln", Ln),
+    ("log2", Log2),
+    ("log10", Log10),
+    ("exp", Exp),
+    ("min2", Min2),
+    ("max2", Max2),
+    ("min", Min),
+    ("max", Max),
+    ("eq", Eq),
+    ("neq", Neq),
+    ("if", If),
+    ("sin", Sin),
+    ("asin", Asin),
+    ("cos", Cos),
+    ("acos", Acos),
+    ("tan", Tan),
+    ("atan", Atan),
+    ("atan2", Atan2),
+    ("dot", Dot),
+    ("el", El),
+    ("push", Push),
+    ("push_front", PushFront),
+    ("\\", RetType),
+    ("vec", VecType),
+    ("rty", Rty),
+    ("vec_op", VecOp),
+];
+
+/// Looks up a built-in symbol by name, or returns a variable.
+fn symbol(name: Arc<String>) -> Symbol {
+    SYMBOLS.iter()
+        .find(|(n, _)| *n == &**name)
+        .map(|(_, sym)| sym.clone())
+        .unwrap_or(Var(name))
+}
+
 fn parse_expr(node: &str, mut convert: Convert, ignored: &mut Vec<Range>) -> Result<(Range, Expr), ()> {
     let start = convert;
     let start_range = convert.start_node(node)?;
@@ -31,80 +118,15 @@ fn parse_expr(node: &str, mut convert: Convert, ignored: &mut Vec<Range>) -> Res
         } else if let Ok((range, val)) = parse_rapp(convert, ignored) {
             convert.update(range);
             expr = Some(val);
+        } else if let Ok((range, val)) = parse_ho(convert, ignored) {
+            convert.update(range);
+            expr = Some(val);
         } else if let Ok((range, val)) = convert.meta_string("var") {
             convert.update(range);
-            expr = Some(Sym(match &**val {
-                "triv" | "∀" | "dom" => Triv,
-                "ex" | "∃" | "codom" => Ex,
-                "false1" => False1,
-                "idb" => Idb,
-                "not" => Not,
-                "true1" => True1,
-                "false2" => False2,
-                "true2" => True2,
-                "and" => And,
-                "or" => Or,
-                "eqb" => Eqb,
-                "xor" => Xor,
-                "nand" => Nand,
-                "nor" => Nor,
-                "exc" => Exc,
-                "imply" => Imply,
-                "fstb" => Fstb,
-                "sndb" => Sndb,
-                "neqb" => Xor,
-                "id" => Id,
-                "lt" => Lt,
-                "le" => Le,
-                "gt" => Gt,
-                "ge" => Ge,
-                "mul" => Mul,
-                "div" => Div,
-                "rem" => Rem,
-                "pow" => Pow,
-                "rpow" => Rpow,
-                "sqrt" => Sqrt,
-                "even" => Even,
-                "odd" => Odd,
-                "neg" => Neg,
-                "add" => Add,
-                "sub" => Sub,
-                "len" => Len,
-                "concat" => Concat,
-                "sum" => Sum,
-                "mul_mat" => MulMat,
-                "det" => Det,
-                "dim" => Dim,
-                "fst" => Fst,
-                "snd" => Snd,
-                "ln" => Ln,
-                "log2" => Log2,
-                "log10" => Log10,
-                "exp" => Exp,
-                "min2" => Min2,
-                "max2" => Max2,
-                "min" => Min,
-                "max" => Max,
-                "eq" => Eq,
-                "neq" => Neq,
-                "if" => If,
-                "sin" => Sin,
-                "asin" => Asin,
-                "cos" => Cos,
-                "acos" => Acos,
-                "tan" => Tan,
-                "atan" => Atan,
-                "atan2" => Atan2,
-                "dot" => Dot,
-                "el" => El,
-                "push" => Push,
-                "push_front" => PushFront,
-                "\\" => RetType,
-                "vec" => VecType,
-                "rty" => Rty,
-                "vec_op" => VecOp,
-                _ => Var(val),
-            }));
+            expr = Some(Sym(symbol(val)));
+        } else if let Ok((range, val)) = convert.meta_string("meta") {
+            convert.update(range);
+            expr = Some(Sym(MetaVar(val)));
         } else if let Ok((range, val)) = convert.meta_bool("bool") {
             convert.update(range);
             expr = Some(val.into());
@@ -200,6 +222,35 @@ fn parse_rapp(mut convert: Convert, ignored: &mut Vec<Range>) -> Result<(Range,
     Ok((convert.subtract(start), app(sym, arg)))
 }
 
+fn parse_ho(mut convert: Convert, ignored: &mut Vec<Range>) -> Result<(Range, Expr), ()> {
+    let start = convert;
+    let node = "ho";
+    let start_range = convert.start_node(node)?;
+    convert.update(start_range);
+
+    let mut f: Option<Arc<String>> = None;
+    let mut args: Vec<Arc<String>> = vec![];
+    loop {
+        if let Ok(range) = convert.end_node(node) {
+            convert.update(range);
+            break;
+        } else if let Ok((range, val)) = convert.meta_string("f") {
+            convert.update(range);
+            f = Some(val);
+        } else if let Ok((range, val)) = convert.meta_string("arg") {
+            convert.update(range);
+            args.push(val);
+        } else {
+            let range = convert.ignore();
+            convert.update(range);
+            ignored.push(range);
+        }
+    }
+
+    let f = f.ok_or(())?;
+    Ok((convert.subtract(start), Sym(HoVar(f, args))))
+}
+
 fn parse_seq(mut convert: Convert, ignored: &mut Vec<Range>) -> Result<(Range, Expr), ()> {
     let start = convert;
     let node = "seq";
@@ -264,6 +315,204 @@ fn parse_seq(mut convert: Convert, ignored: &mut Vec<Range>) -> Result<(Range, E
     Ok((convert.subtract(start), Op(op, Box::new(left), Box::new(right))))
 }
 
+fn parse_def(mut convert: Convert, ignored: &mut Vec<Range>) -> Result<(Range, Knowledge), ()> {
+    let start = convert;
+    let node = "def";
+    let start_range = convert.start_node(node)?;
+    convert.update(start_range);
+
+    let mut name: Option<Symbol> = None;
+    let mut expr: Option<Expr> = None;
+    loop {
+        if let Ok(range) = convert.end_node(node) {
+            convert.update(range);
+            break;
+        } else if let Ok((range, val)) = parse_expr("name", convert, ignored) {
+            convert.update(range);
+            if let Sym(val) = val {
+                name = Some(val);
+            }
+        } else if let Ok((range, val)) = parse_expr("expr", convert, ignored) {
+            convert.update(range);
+            expr = Some(val);
+        } else {
+            let range = convert.ignore();
+            convert.update(range);
+            ignored.push(range);
+        }
+    }
+
+    let name = name.ok_or(())?;
+    let expr = expr.ok_or(())?;
+    Ok((convert.subtract(start), Def(name, expr)))
+}
+
+fn parse_rule(mut convert: Convert, ignored: &mut Vec<Range>) -> Result<(Range, Knowledge), ()> {
+    let start = convert;
+    let node = "rule";
+    let start_range = convert.start_node(node)?;
+    convert.update(start_range);
+
+    let mut eqv: Option<bool> = None;
+    let mut left: Option<Expr> = None;
+    let mut right: Option<Expr> = None;
+    let mut cond: Option<Expr> = None;
+    loop {
+        if let Ok(range) = convert.end_node(node) {
+            convert.update(range);
+            break;
+        } else if let Ok((range, val)) = parse_expr("left", convert, ignored) {
+            convert.update(range);
+            left = Some(val);
+        } else if let Ok((range, val)) = parse_expr("right", convert, ignored) {
+            convert.update(range);
+            right = Some(val);
+        } else if let Ok((range, val)) = parse_expr("cond", convert, ignored) {
+            convert.update(range);
+            cond = Some(val);
+        } else if let Ok((range, _)) = convert.meta_bool("eqv") {
+            convert.update(range);
+            eqv = Some(true);
+        } else if let Ok((range, _)) = convert.meta_bool("red") {
+            convert.update(range);
+            eqv = Some(false);
+        } else {
+            let range = convert.ignore();
+            convert.update(range);
+            ignored.push(range);
+        }
+    }
+
+    let eqv = eqv.ok_or(())?;
+    let left = left.ok_or(())?;
+    let right = right.ok_or(())?;
+    Ok((convert.subtract(start), match (eqv, cond) {
+        (true, None) => Eqv(left, right),
+        (false, None) => Red(left, right),
+        (false, Some(cond)) => CondRed(left, right, cond),
+        (true, Some(_)) => return Err(()),
+    }))
+}
+
+fn parse_tactics(
+    node: &str,
+    mut convert: Convert,
+    ignored: &mut Vec<Range>
+) -> Result<(Range, Tactic), ()> {
+    let start = convert;
+    let start_range = convert.start_node(node)?;
+    convert.update(start_range);
+
+    let mut tactics: Vec<Tactic> = vec![];
+    loop {
+        if let Ok(range) = convert.end_node(node) {
+            convert.update(range);
+            break;
+        } else if let Ok((range, val)) = parse_tactic("tactic", convert, ignored) {
+            convert.update(range);
+            tactics.push(val);
+        } else {
+            let range = convert.ignore();
+            convert.update(range);
+            ignored.push(range);
+        }
+    }
+
+    let tactic = tactics.into_iter().reduce(|a, b| Tactic::Then(Box::new(a), Box::new(b)));
+    Ok((convert.subtract(start), tactic.ok_or(())?))
+}
+
+fn parse_tactic(
+    node: &str,
+    mut convert: Convert,
+    ignored: &mut Vec<Range>
+) -> Result<(Range, Tactic), ()> {
+    let start = convert;
+    let start_range = convert.start_node(node)?;
+    convert.update(start_range);
+
+    let mut tactic: Option<Tactic> = None;
+    let mut first: Vec<Tactic> = vec![];
+    loop {
+        if let Ok(range) = convert.end_node(node) {
+            convert.update(range);
+            break;
+        } else if let Ok((range, _)) = convert.meta_bool("reduce") {
+            convert.update(range);
+            tactic = Some(Tactic::Reduce);
+        } else if let Ok((range, _)) = convert.meta_bool("decide") {
+            convert.update(range);
+            tactic = Some(Tactic::Decide);
+        } else if let Ok((range, val)) = parse_rewrite(convert, ignored) {
+            convert.update(range);
+            tactic = Some(val);
+        } else if let Ok((range, val)) = convert.meta_string("inline") {
+            convert.update(range);
+            tactic = Some(Tactic::Inline(symbol(val)));
+        } else if let Ok((range, val)) = parse_tactic("repeat", convert, ignored) {
+            convert.update(range);
+            tactic = Some(Tactic::Repeat(Box::new(val)));
+        } else if let Ok((range, val)) = parse_tactic("try", convert, ignored) {
+            convert.update(range);
+            tactic = Some(Tactic::Try(Box::new(val)));
+        } else if let Ok((range, val)) = parse_tactics("first", convert, ignored) {
+            convert.update(range);
+            first.push(val);
+        } else if let Ok((range, val)) = parse_tactics("then", convert, ignored) {
+            convert.update(range);
+            tactic = Some(val);
+        } else {
+            let range = convert.ignore();
+            convert.update(range);
+            ignored.push(range);
+        }
+    }
+
+    if !first.is_empty() {tactic = Some(Tactic::First(first))};
+    let tactic = tactic.ok_or(())?;
+    Ok((convert.subtract(start), tactic))
+}
+
+fn parse_rewrite(mut convert: Convert, ignored: &mut Vec<Range>) -> Result<(Range, Tactic), ()> {
+    let start = convert;
+    let node = "rewrite";
+    let start_range = convert.start_node(node)?;
+    convert.update(start_range);
+
+    let mut dir = Dir::Forward;
+    let mut rule: Option<RuleRef> = None;
+    let mut pos: Option<Pos> = None;
+    loop {
+        if let Ok(range) = convert.end_node(node) {
+            convert.update(range);
+            break;
+        } else if let Ok((range, _)) = convert.meta_bool("backward") {
+            convert.update(range);
+            dir = Dir::Backward;
+        } else if let Ok((range, val)) = convert.meta_f64("index") {
+            convert.update(range);
+            if val < 0.0 || val.fract() != 0.0 {return Err(())};
+            rule = Some(RuleRef::Index(val as usize));
+        } else if let Ok((range, val)) = convert.meta_string("rule") {
+            convert.update(range);
+            match parse_knowledge_str(&val).map_err(|_| ())?.as_slice() {
+                [k] => rule = Some(RuleRef::Rule(k.clone())),
+                _ => return Err(()),
+            }
+        } else if let Ok((range, val)) = convert.meta_string("pos") {
+            convert.update(range);
+            pos = Some(val.parse().map_err(|_| ())?);
+        } else {
+            let range = convert.ignore();
+            convert.update(range);
+            ignored.push(range);
+        }
+    }
+
+    let rule = rule.ok_or(())?;
+    Ok((convert.subtract(start), Tactic::Rewrite(rule, dir, pos)))
+}
+
 /// Parses a string.
 pub fn parse_str(data: &str) -> Result<Expr, String> {
     use piston_meta::{parse_errstr, syntax_errstr};
@@ -296,3 +545,122 @@ pub fn parse(source: &str) -> Result<Expr, String> {
 
     parse_str(&data)
 }
+
+/// Parses knowledge from a string.
+///
+/// Each line contains a definition `a := b`, a reduction `a => b`,
+/// an equivalence `a <=> b` or a comment starting with `//`.
+pub fn parse_knowledge_str(data: &str) -> Result<Vec<Knowledge>, String> {
+    Ok(parse_knowledge_lines(data)?.into_iter().map(|(_, k)| k).collect())
+}
+
+/// Parses knowledge from a string into rules,
+/// recording the file name and the line of each rule.
+pub fn parse_rules_str(data: &str, file: &str) -> Result<Vec<Rule>, String> {
+    Ok(parse_knowledge_lines(data)?.into_iter()
+        .map(|(line, k)| Rule::new(k).source(file, line)).collect())
+}
+
+/// Parses knowledge with the line where it starts.
+fn parse_knowledge_lines(data: &str) -> Result<Vec<(usize, Knowledge)>, String> {
+    use piston_meta::{parse_errstr, syntax_errstr};
+
+    let syntax_src = format!("{}\n{}",
+        include_str!("../assets/syntax.txt"),
+        include_str!("../assets/knowledge-syntax.txt"));
+    let syntax = syntax_errstr(&syntax_src)?;
+
+    let mut meta_data = vec![];
+    parse_errstr(&syntax, data, &mut meta_data)?;
+
+    let mut convert = Convert::new(&meta_data);
+    let mut ignored = vec![];
+    let mut res = vec![];
+    loop {
+        let line = meta_data.get(meta_data.len() - convert.remaining_data_len())
+            .map(|m| 1 + data[..m.offset].matches('\n').count()).unwrap_or(0);
+        if let Ok((range, val)) = parse_def(convert, &mut ignored) {
+            convert.update(range);
+            res.push((line, val));
+        } else if let Ok((range, val)) = parse_rule(convert, &mut ignored) {
+            convert.update(range);
+            res.push((line, val));
+        } else if convert.remaining_data_len() > 0 {
+            return Err("Could not convert meta data".into());
+        } else {
+            break;
+        }
+    }
+    Ok(res)
+}
+
+/// Parses knowledge from a source file.
+pub fn parse_knowledge(source: &str) -> Result<Vec<Knowledge>, String> {
+    parse_knowledge_str(&read_source(source)?)
+}
+
+/// Parses rules from a source file, see `parse_rules_str`.
+pub fn parse_rules(source: &str) -> Result<Vec<Rule>, String> {
+    parse_rules_str(&read_source(source)?, source)
+}
+
+fn read_source(source: &str) -> Result<String, String> {
+    use std::fs::File;
+    use std::io::Read;
+
+    let mut data_file = File::open(source).map_err(|err|
+        format!("Could not open `{}`, {}", source, err))?;
+    let mut data = String::new();
+    data_file.read_to_string(&mut data).map_err(|err|
+        format!("Could not read `{}`, {}", source, err))?;
+    Ok(data)
+}
+
+/// Parses a tactic script.
+///
+/// Tactics are separated by `;` or `then`, e.g.
+/// `repeat reduce; rewrite <- "a <=> b" at 0.1; first [inline not, reduce]`.
+/// A rule is referred to by index or by its text in quotes.
+pub fn parse_tactic_str(data: &str) -> Result<Tactic, String> {
+    use piston_meta::{parse_errstr, syntax_errstr};
+
+    let syntax_src = format!("{}\n{}\n{}",
+        include_str!("../assets/syntax.txt"),
+        include_str!("../assets/knowledge-syntax.txt"),
+        include_str!("../assets/tactic-syntax.txt"));
+    let syntax = syntax_errstr(&syntax_src)?;
+
+    let mut meta_data = vec![];
+    parse_errstr(&syntax, data, &mut meta_data)?;
+
+    let convert = Convert::new(&meta_data);
+    let mut ignored = vec![];
+    match parse_tactics("tactics", convert, &mut ignored) {
+        Err(()) => Err("Could not convert meta data".into()),
+        Ok((_, tactic)) => Ok(tactic),
+    }
+}
+
+/// Parses a query `a = b`, where the expressions may contain metavariables, e.g. `and[not] = ?h`.
+///
+/// See `solve`.
+pub fn parse_query_str(data: &str) -> Result<(Expr, Expr), String> {
+    use piston_meta::{parse_errstr, syntax_errstr};
+
+    let syntax_src = format!("{}\n{}",
+        include_str!("../assets/syntax.txt"),
+        include_str!("../assets/query-syntax.txt"));
+    let syntax = syntax_errstr(&syntax_src)?;
+
+    let mut meta_data = vec![];
+    parse_errstr(&syntax, data, &mut meta_data)?;
+
+    let mut convert = Convert::new(&meta_data);
+    let mut ignored = vec![];
+    let (range, left) = parse_expr("left", convert, &mut ignored)
+        .map_err(|()| String::from("Could not convert meta data"))?;
+    convert.update(range);
+    let (_, right) = parse_expr("right", convert, &mut ignored)
+        .map_err(|()| String::from("Could not convert meta data"))?;
+    Ok((left, right))
+}
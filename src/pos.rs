@@ -0,0 +1,182 @@
+use std::fmt;
+use std::str::FromStr;
+
+use super::*;
+
+/// The position of a sub-expression, as a sequence of child indices.
+///
+/// For operators, `0` is the left argument and `1` is the right argument.
+/// For tuples and lists, the index is the item.
+/// The empty sequence is the root.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pos(pub Vec<usize>);
+
+impl Pos {
+    /// The position of the whole expression.
+    pub fn root() -> Pos {Pos(vec![])}
+
+    /// Returns `true` if this is the root position.
+    pub fn is_root(&self) -> bool {self.0.is_empty()}
+
+    /// Returns the position of the parent, or `None` for the root.
+    pub fn parent(&self) -> Option<Pos> {
+        self.0.split_last().map(|(_, parent)| Pos(parent.into()))
+    }
+
+    /// Returns the position of a child.
+    pub fn child(&self, i: usize) -> Pos {
+        let mut pos = self.clone();
+        pos.0.push(i);
+        pos
+    }
+}
+
+impl fmt::Display for Pos {
+    fn fmt(&self, w: &mut fmt::Formatter<'_>) -> std::result::Result<(), fmt::Error> {
+        if self.is_root() {return write!(w, "root")};
+        for (i, p) in self.0.iter().enumerate() {
+            if i > 0 {write!(w, ".")?}
+            write!(w, "{}", p)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Pos {
+    type Err = String;
+
+    /// Parses a position, e.g. `0.1.2` or `root`.
+    fn from_str(s: &str) -> Result<Pos, String> {
+        let s = s.trim();
+        if s == "root" || s.is_empty() {return Ok(Pos::root())};
+        s.split('.').map(|n| n.trim().parse::<usize>()
+            .map_err(|_| format!("Expected position, e.g. `0.1`, found `{}`", s)))
+            .collect::<Result<Vec<usize>, String>>()
+            .map(Pos)
+    }
+}
+
+/// The direction to use a rule.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Dir {
+    /// From the left side to the right side.
+    Forward,
+    /// From the right side to the left side.
+    ///
+    /// This is only allowed for equivalences.
+    Backward,
+}
+
+impl Expr {
+    /// Returns the children of the expression.
+    pub fn children(&self) -> Vec<&Expr> {
+        match self {
+            Sym(_) | Ret(_) => vec![],
+            Op(_, a, b) => vec![&**a, &**b],
+            Tup(items) | List(items) => items.iter().collect(),
+        }
+    }
+
+    /// Returns the sub-expression at a position.
+    pub fn get(&self, pos: &Pos) -> Option<&Expr> {
+        let mut expr = self;
+        for &i in &pos.0 {
+            expr = match expr {
+                Op(_, a, _) if i == 0 => a,
+                Op(_, _, b) if i == 1 => b,
+                Tup(items) | List(items) => items.get(i)?,
+                _ => return None,
+            };
+        }
+        Some(expr)
+    }
+
+    /// Returns a new expression where the sub-expression at a position is replaced.
+    pub fn replace(&self, pos: &Pos, new: Expr) -> Option<Expr> {
+        self.replace_from(&pos.0, new)
+    }
+
+    fn replace_from(&self, pos: &[usize], new: Expr) -> Option<Expr> {
+        if pos.is_empty() {return Some(new)};
+        let i = pos[0];
+        Some(match self {
+            Op(op, a, b) if i == 0 =>
+                Op(*op, Box::new(a.replace_from(&pos[1..], new)?), b.clone()),
+            Op(op, a, b) if i == 1 =>
+                Op(*op, a.clone(), Box::new(b.replace_from(&pos[1..], new)?)),
+            Tup(items) | List(items) => {
+                let mut items = items.clone();
+                let n = items.get(i)?.replace_from(&pos[1..], new)?;
+                items[i] = n;
+                if let List(_) = self {List(items)} else {Tup(items)}
+            }
+            _ => return None,
+        })
+    }
+
+    /// Returns all positions in the expression, with parents before children.
+    pub fn positions(&self) -> Vec<Pos> {
+        let mut res = vec![];
+        self.collect_positions(Pos::root(), &mut res);
+        res
+    }
+
+    fn collect_positions(&self, pos: Pos, res: &mut Vec<Pos>) {
+        let children = self.children();
+        res.push(pos.clone());
+        for (i, child) in children.into_iter().enumerate() {
+            child.collect_positions(pos.child(i), res);
+        }
+    }
+
+    /// Rewrites the sub-expression at a position, using a rule in some direction.
+    pub fn rewrite_at(
+        &self,
+        knowledge: &[Knowledge],
+        pos: &Pos,
+        rule: usize,
+        dir: Dir
+    ) -> Result<Expr, Error> {
+        self.rewrite_at_env(knowledge, &Env::new(knowledge), pos, rule, dir)
+    }
+
+    /// Rewrites the sub-expression at a position in an environment.
+    pub(crate) fn rewrite_at_env(
+        &self,
+        knowledge: &[Knowledge],
+        env: &Env,
+        pos: &Pos,
+        rule: usize,
+        dir: Dir
+    ) -> Result<Expr, Error> {
+        let expr = self.get(pos).ok_or(Error::InvalidPosition)?;
+        let (a, b) = match (knowledge.get(rule), dir) {
+            (Some(CondRed(a, b, c)), Dir::Forward) => {
+                let mut ctx = Context::with_ac(&env.ac);
+                if !ctx.bind(a, expr) {return Err(Error::NoReductionRule)};
+                ctx.condition_env(c, knowledge, env)?;
+                let new = ctx.substitute_in(b, knowledge)?;
+                return self.replace(pos, new).ok_or(Error::InvalidPosition);
+            }
+            (Some(Red(a, b)), Dir::Forward) |
+            (Some(Eqv(a, b)), Dir::Forward) |
+            (Some(Eqv(b, a)), Dir::Backward) => (a, b),
+            _ => return Err(Error::InvalidRule),
+        };
+        let mut ctx = Context::with_ac(&env.ac);
+        if !ctx.bind(a, expr) {return Err(Error::NoReductionRule)};
+        let new = ctx.substitute_in(b, knowledge)?;
+        self.replace(pos, new).ok_or(Error::InvalidPosition)
+    }
+
+    /// Returns the discharged condition when a conditional reduction is used at a position.
+    pub fn condition_at(&self, knowledge: &[Knowledge], pos: &Pos, rule: usize) -> Option<Expr> {
+        if let (Some(CondRed(a, _, c)), Some(expr)) = (knowledge.get(rule), self.get(pos)) {
+            let mut ctx = Context::with_ac(&ac_symbols(knowledge));
+            if ctx.bind(a, expr) {return ctx.condition(c, knowledge).ok()};
+        }
+        None
+    }
+}
@@ -0,0 +1,170 @@
+use std::collections::HashSet;
+
+use super::*;
+
+/// Values of metavariables that solve a query.
+pub type Solution = Vec<(Arc<String>, Expr)>;
+
+/// The default number of expressions explored on each side by `solve`.
+pub const SOLVE_LIMIT: usize = 200;
+
+impl Expr {
+    /// Returns the metavariables in the expression, in order of first occurrence.
+    pub fn metas(&self) -> Vec<Arc<String>> {
+        let mut res = vec![];
+        self.collect_metas(&mut res);
+        res
+    }
+
+    fn collect_metas(&self, res: &mut Vec<Arc<String>>) {
+        match self {
+            Sym(MetaVar(x)) => if !res.contains(x) {res.push(x.clone())},
+            _ => for a in self.children() {a.collect_metas(res)},
+        }
+    }
+}
+
+/// An expression with the values of metavariables that were assumed to reach it.
+type State = (Expr, Solution);
+
+/// Finds values of metavariables that make two expressions equal.
+///
+/// For example, `and[not] = ?h` has the solution `?h = or`.
+/// Both sides are rewritten with reductions and equivalences at every position,
+/// exploring up to `limit` expressions on each side, and then unified, closest first.
+///
+/// Rules are also used for narrowing: When a sub-expression with metavariables
+/// unifies with the side of a rule, the metavariables are instantiated,
+/// e.g. `?f[not] = or` has the solution `?f = and` from `and[not] => or`.
+/// Narrowing only uses rules where the variables are plain variables,
+/// and only instantiates metavariables with closed expressions.
+///
+/// Equations without metavariables between closed boolean expressions
+/// are decided by `boolean_eqv` instead, with one empty solution when they hold.
+///
+/// Returns the solutions without duplicates.
+/// A solution binds the metavariables of the query that got a value.
+/// Solutions where every value is fully reduced come first,
+/// so `?h = or` comes before the trivial `?h = and[not]`.
+pub fn solve(left: &Expr, right: &Expr, knowledge: &[Knowledge], limit: usize) -> Vec<Solution> {
+    let env = Env::new(knowledge);
+    let mut metas = left.metas();
+    for x in right.metas() {if !metas.contains(&x) {metas.push(x)}}
+    if metas.is_empty() {
+        // Closed boolean equations are decided by comparing normal forms,
+        // falling back to the search when they can not be decided.
+        if let Ok(eqv) = boolean_eqv(left, right, knowledge) {
+            return if eqv {vec![vec![]]} else {vec![]};
+        }
+    }
+
+    let left = reachable(left, knowledge, &env, limit);
+    let right = reachable(right, knowledge, &env, limit);
+    let mut res: Vec<Solution> = vec![];
+    for d in 0..left.len() + right.len() - 1 {
+        for i in d.saturating_sub(right.len() - 1)..left.len().min(d + 1) {
+            let ((a, a_vars), (b, b_vars)) = (&left[i], &right[d - i]);
+            let mut ctx = Context {vars: a_vars.clone(), ac: vec![]};
+            if !b_vars.iter().all(|(x, b)| ctx.unify(&Sym(MetaVar(x.clone())), b)) ||
+               !ctx.unify(a, b) {continue};
+            let solution: Solution = metas.iter()
+                .map(|x| (x.clone(), ctx.substitute_metas(&Sym(MetaVar(x.clone())))))
+                .filter(|(x, a)| a != &Sym(MetaVar(x.clone())))
+                .collect();
+            if !res.contains(&solution) {res.push(solution)};
+        }
+    }
+    // Stable, so solutions are closest first within each group.
+    res.sort_by_key(|solution| solution.iter().any(|(_, a)| a.reduce_env(knowledge, &env).is_ok()));
+    res
+}
+
+/// Returns expressions reachable by reductions, equivalences and narrowing,
+/// shortest proofs first.
+fn reachable(expr: &Expr, knowledge: &[Knowledge], env: &Env, limit: usize) -> Vec<State> {
+    let mut res: Vec<State> = vec![(expr.clone(), vec![])];
+    let mut seen: HashSet<String> = HashSet::new();
+    seen.insert(format!("{}", expr));
+    let mut fresh = 0;
+    let mut i = 0;
+    'search: while i < res.len() {
+        let (expr, vars) = res[i].clone();
+        for pos in expr.positions() {
+            let sub = expr.get(&pos).unwrap();
+            // Narrowing a metavariable directly would instantiate it with every rule.
+            let narrow = !matches!(sub, Sym(MetaVar(_))) && !sub.metas().is_empty();
+            for (j, k) in knowledge.iter().enumerate() {
+                let dirs: &[(Dir, &Expr, &Expr)] = match k {
+                    Red(a, b) => &[(Dir::Forward, a, b)],
+                    CondRed(a, b, _) => &[(Dir::Forward, a, b)],
+                    Eqv(a, b) => &[(Dir::Forward, a, b), (Dir::Backward, b, a)],
+                    Def(..) => &[],
+                };
+                for &(dir, a, b) in dirs.iter().filter(|(_, _, b)| !has_any(b)) {
+                    let mut new: Vec<State> = vec![];
+                    if let Ok(e) = expr.rewrite_at_env(knowledge, env, &pos, j, dir) {
+                        new.push((e, vars.clone()));
+                    }
+                    if let (true, Red(..) | Eqv(..)) = (narrow, k) {
+                        fresh += 1;
+                        new.extend(narrow_at(&expr, &vars, &pos, a, b, fresh));
+                    }
+                    for (e, vars) in new {
+                        let key = vars.iter().fold(format!("{}", e), |key, (x, a)| {
+                            format!("{} ?{}={}", key, x, a)
+                        });
+                        if !seen.insert(key) {continue};
+                        res.push((e, vars));
+                        if res.len() >= limit {break 'search};
+                    }
+                }
+            }
+        }
+        i += 1;
+    }
+    res
+}
+
+/// Rewrites a sub-expression by unifying it with the side `a` of a rule.
+///
+/// Succeeds only when metavariables of the sub-expression are instantiated with closed expressions.
+fn narrow_at(
+    expr: &Expr,
+    vars: &Solution,
+    pos: &Pos,
+    a: &Expr,
+    b: &Expr,
+    fresh: usize
+) -> Option<State> {
+    let sub = expr.get(pos)?;
+    let (a, b) = (rename(a, fresh)?, rename(b, fresh)?);
+    let mut ctx = Context {vars: vars.clone(), ac: vec![]};
+    if !ctx.unify(&a, sub) {return None};
+    let metas = sub.metas();
+    let meta = |x: &Arc<String>| Sym(MetaVar(x.clone()));
+    let values: Solution = metas.iter()
+        .map(|x| (x.clone(), ctx.substitute_metas(&meta(x))))
+        .filter(|(x, a)| a != &meta(x)).collect();
+    // Only closed values are used, since general values make the search diverge.
+    if values.is_empty() || values.iter().any(|(_, a)| !a.metas().is_empty()) {return None};
+    let mut new_vars: Solution = vars.iter()
+        .map(|(x, a)| (x.clone(), ctx.substitute_metas(a))).collect();
+    new_vars.extend(values);
+    Some((ctx.substitute_metas(&expr.replace(pos, b)?), new_vars))
+}
+
+/// Replaces the variables of a rule with fresh metavariables, for narrowing.
+///
+/// Returns `None` when the rule uses other kinds of variables.
+fn rename(a: &Expr, fresh: usize) -> Option<Expr> {
+    Some(match a {
+        Sym(Var(x)) => Sym(MetaVar(Arc::new(format!("{}'{}", x, fresh)))),
+        Sym(Any) | Sym(ListVar(_)) | Sym(Singleton(_)) | Sym(HeadTailTup(..)) |
+        Sym(HeadTailList(..)) | Sym(RetVar(_)) | Sym(BinopRetVar(..)) | Sym(UnopRetVar(..)) |
+        Sym(NoConstrVar(_)) | Sym(HoVar(..)) | Sym(Hole(_)) => return None,
+        Sym(_) | Ret(_) => a.clone(),
+        Op(op, a, b) => Op(*op, Box::new(rename(a, fresh)?), Box::new(rename(b, fresh)?)),
+        Tup(items) => Tup(items.iter().map(|a| rename(a, fresh)).collect::<Option<_>>()?),
+        List(items) => List(items.iter().map(|a| rename(a, fresh)).collect::<Option<_>>()?),
+    })
+}
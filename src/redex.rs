@@ -0,0 +1,54 @@
+use super::*;
+
+/// A sub-expression that can be reduced by some rule.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Redex {
+    /// The position of the sub-expression.
+    pub pos: Pos,
+    /// The index of the rule in the knowledge base.
+    pub rule: usize,
+    /// The variables bound by the left side of the rule.
+    pub bindings: Vec<(Arc<String>, Expr)>,
+    /// The whole expression after the reduction.
+    pub expr: Expr,
+}
+
+impl Expr {
+    /// Returns every reduction that applies to the expression or one of its sub-expressions.
+    ///
+    /// Positions are visited with parents before children,
+    /// and rules in the order of the knowledge base.
+    /// Unlike `reduce`, this does not stop at the first rule that matches.
+    /// Conditional reductions are included when their condition holds.
+    pub fn redexes<'a>(
+        &'a self,
+        knowledge: &'a [Knowledge]
+    ) -> impl Iterator<Item = Redex> + 'a {
+        let env = Env::new(knowledge);
+        self.positions().into_iter().flat_map(move |pos| {
+            let mut ctx = Context::with_ac(&env.ac);
+            let env = env.clone();
+            knowledge.iter().enumerate().filter_map(move |(rule, k)| {
+                let sub = self.get(&pos)?;
+                ctx.vars.clear();
+                let new = match k {
+                    Red(a, b) if ctx.bind(a, sub) => {
+                        ctx.substitute_in(b, knowledge).ok()?
+                    }
+                    CondRed(a, b, c) if ctx.bind(a, sub) => {
+                        ctx.condition_env(c, knowledge, &env).ok()?;
+                        ctx.substitute_in(b, knowledge).ok()?
+                    }
+                    _ => return None,
+                };
+                Some(Redex {
+                    pos: pos.clone(),
+                    rule,
+                    bindings: ctx.vars.clone(),
+                    expr: self.replace(&pos, new)?,
+                })
+            })
+        })
+    }
+}
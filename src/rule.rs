@@ -0,0 +1,93 @@
+use std::fmt;
+
+use super::*;
+
+/// Metadata about a rule.
+#[derive(Clone, PartialEq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Meta {
+    /// A stable name, e.g. `and-not`.
+    pub name: Option<Arc<String>>,
+    /// Tags, e.g. `boolean` or `vector`.
+    pub tags: Vec<Arc<String>>,
+    /// Rules with higher priority are tried first.
+    pub priority: i32,
+    /// The file and line where the rule was defined.
+    pub source: Option<(Arc<String>, usize)>,
+}
+
+impl Meta {
+    /// Returns `true` if the rule has some name.
+    pub fn is_named(&self, name: &str) -> bool {
+        self.name.as_ref().map(|n| &***n == name).unwrap_or(false)
+    }
+
+    /// Returns `true` if the rule has some tag.
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| &***t == tag)
+    }
+}
+
+/// Knowledge with metadata.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rule {
+    /// The knowledge.
+    pub knowledge: Knowledge,
+    /// The metadata.
+    pub meta: Meta,
+}
+
+impl From<Knowledge> for Rule {
+    fn from(knowledge: Knowledge) -> Rule {Rule::new(knowledge)}
+}
+
+impl Rule {
+    /// Creates a new rule without metadata.
+    pub fn new(knowledge: Knowledge) -> Rule {
+        Rule {knowledge, meta: Meta::default()}
+    }
+
+    /// Sets the name of the rule.
+    pub fn name<A: Into<String>>(mut self, name: A) -> Rule {
+        self.meta.name = Some(Arc::new(name.into()));
+        self
+    }
+
+    /// Adds a tag to the rule.
+    pub fn tag<A: Into<String>>(mut self, tag: A) -> Rule {
+        let tag = tag.into();
+        if !self.meta.has_tag(&tag) {self.meta.tags.push(Arc::new(tag))};
+        self
+    }
+
+    /// Sets the priority of the rule.
+    pub fn priority(mut self, priority: i32) -> Rule {
+        self.meta.priority = priority;
+        self
+    }
+
+    /// Sets the file and line where the rule was defined.
+    pub fn source<A: Into<String>>(mut self, file: A, line: usize) -> Rule {
+        self.meta.source = Some((Arc::new(file.into()), line));
+        self
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, w: &mut fmt::Formatter<'_>) -> std::result::Result<(), fmt::Error> {
+        if let Some(name) = &self.meta.name {write!(w, "{}: ", name)?};
+        write!(w, "{}", self.knowledge)
+    }
+}
+
+/// Selects the rules that satisfy a predicate, ordered by priority.
+///
+/// Rules with the same priority keep their order.
+/// For example, `select(&rules, |r| r.meta.has_tag("boolean"))` gives only the boolean rules,
+/// and `select(&rules, |r| !r.meta.is_named("dot-product"))` disables a rule.
+pub fn select<F: Fn(&Rule) -> bool>(rules: &[Rule], f: F) -> Vec<Knowledge> {
+    let mut res: Vec<&Rule> = rules.iter().filter(|r| f(r)).collect();
+    res.sort_by_key(|r| std::cmp::Reverse(r.meta.priority));
+    res.into_iter().map(|r| r.knowledge.clone()).collect()
+}
@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+
+use super::*;
+
+/// An expression with the previous expression, the justification and the condition of the step.
+type State = (Expr, Option<(usize, Justification, Option<Expr>)>);
+
+/// The default number of expressions explored by `simplify`.
+pub const SIMPLIFY_LIMIT: usize = 1000;
+
+/// Finds the cheapest expression equivalent to some expression.
+///
+/// Returns the cost and the steps from the expression to the cheapest one.
+/// See `simplify_with_limit`.
+pub fn simplify(expr: &Expr, knowledge: &[Knowledge], cost: &dyn Cost) -> (f64, Trace) {
+    simplify_with_limit(expr, knowledge, cost, SIMPLIFY_LIMIT)
+}
+
+/// Finds the cheapest expression equivalent to some expression,
+/// exploring up to a limited number of expressions.
+///
+/// Reductions and equivalences are used at every position, shortest proofs first.
+/// Like `EGraph::saturate`, equivalences are not used on functions applied to arguments.
+pub fn simplify_with_limit(
+    expr: &Expr,
+    knowledge: &[Knowledge],
+    cost: &dyn Cost,
+    limit: usize
+) -> (f64, Trace) {
+    let env = Env::new(knowledge);
+    // The rules in every direction they can be used, with the head of the matched side.
+    let mut rules: Vec<(usize, Dir, Head)> = vec![];
+    for (j, k) in knowledge.iter().enumerate() {
+        match k {
+            Red(a, b) | CondRed(a, b, _) if !has_any(b) => rules.push((j, Dir::Forward, head(a))),
+            Eqv(a, b) => {
+                if !has_any(b) {rules.push((j, Dir::Forward, head(a)))};
+                if !has_any(a) {rules.push((j, Dir::Backward, head(b)))};
+            }
+            _ => {}
+        }
+    }
+
+    let mut states: Vec<State> = vec![(expr.clone(), None)];
+    // Indices of states by hash.
+    let mut seen: HashMap<u64, Vec<usize>> = HashMap::new();
+    seen.entry(expr.hash_u64()).or_default().push(0);
+    let mut best = (expr.cost(cost), 0);
+    let mut i = 0;
+    'search: while i < states.len() {
+        let expr = states[i].0.clone();
+        for pos in expr.positions() {
+            let sub = if let Some(sub) = expr.get(&pos) {sub} else {continue};
+            let sub_head = head(sub);
+            let applied = pos.0.last() == Some(&0) &&
+                matches!(pos.parent().and_then(|p| expr.get(&p)), Some(Op(Apply, _, _)));
+            for &(j, dir, ref h) in &rules {
+                if applied && matches!(knowledge[j], Eqv(..)) {continue};
+                if !h.matches(&sub_head) {continue};
+                let new = if let Ok(new) = expr.rewrite_at_env(knowledge, &env, &pos, j, dir) {new}
+                    else {continue};
+                let h = new.hash_u64();
+                let ids = seen.entry(h).or_default();
+                if ids.iter().any(|&id| states[id].0 == new) {continue};
+                ids.push(states.len());
+                let c = new.cost(cost);
+                if c < best.0 {best = (c, states.len())};
+                let justification = Justification::Rewrite(knowledge[j].clone(), dir, pos.clone());
+                let condition = expr.condition_at(knowledge, &pos, j);
+                states.push((new, Some((i, justification, condition))));
+                if states.len() >= limit {break 'search};
+            }
+        }
+        i += 1;
+    }
+
+    let mut steps = vec![];
+    let mut id = best.1;
+    while let Some((prev, justification, condition)) = &states[id].1 {
+        steps.push(Step {
+            expr: states[id].0.clone(),
+            justification: justification.clone(),
+            condition: condition.clone(),
+        });
+        id = *prev;
+    }
+    steps.reverse();
+    (best.0, Trace {start: expr.clone(), steps})
+}
+
+/// The head of an expression, used to skip rules that can not match.
+///
+/// This is the number of arguments that a function is applied to,
+/// and the function when it is not an application.
+struct Head<'a> {
+    args: usize,
+    function: &'a Expr,
+}
+
+impl Head<'_> {
+    /// Returns `false` if a pattern with this head can not match an expression with another head.
+    fn matches(&self, other: &Head) -> bool {
+        match (self.function, other.function) {
+            (Sym(a), _) if is_pattern_var(a) => self.args <= other.args,
+            _ if self.args != other.args => false,
+            (Sym(a), Sym(b)) => a == b,
+            (Op(a, _, _), Op(b, _, _)) => a == b,
+            (Ret(_), Ret(_)) | (Tup(_), Tup(_)) | (List(_), List(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+fn head(expr: &Expr) -> Head<'_> {
+    match expr {
+        Op(Apply, f, _) => {
+            let h = head(f);
+            Head {args: h.args + 1, ..h}
+        }
+        _ => Head {args: 0, function: expr},
+    }
+}
+
+/// Returns `true` if a symbol in a pattern can match expressions that are not the symbol itself.
+fn is_pattern_var(sym: &Symbol) -> bool {
+    matches!(sym, Var(_) | NoConstrVar(_) | HoVar(..) | RetVar(_) | Singleton(_) | ListVar(_) |
+        HeadTailTup(..) | HeadTailList(..) | Any | MetaVar(_) | BinopRetVar(..) | UnopRetVar(..))
+}
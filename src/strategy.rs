@@ -0,0 +1,210 @@
+use super::*;
+
+/// Strategy for picking which sub-expression to reduce.
+///
+/// A reduction rule can often be applied at several places in an expression.
+/// Some theories only normalize under a specific strategy.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Strategy {
+    /// Tries the root first, then the first reducible child from left to right.
+    ///
+    /// When both the root and a child can be reduced,
+    /// the reduction with the lowest rule index is preferred.
+    /// Since children are reduced recursively with the same strategy,
+    /// a reducible right child is not considered when the left child is reducible.
+    ///
+    /// This is the strategy used by `Expr::reduce`.
+    #[default]
+    Standard,
+    /// Reduces the leftmost sub-expression that has no reducible children.
+    LeftmostInnermost,
+    /// Reduces the leftmost sub-expression that is not inside a reducible expression.
+    LeftmostOutermost,
+    /// Reduces all outermost reducible sub-expressions in one step.
+    ///
+    /// The returned rule index is the one used for the leftmost reduction.
+    ParallelOutermost,
+    /// Reduces the sub-expression that matches the rule with the lowest index.
+    ///
+    /// When several sub-expressions match the same rule,
+    /// the leftmost outermost is reduced.
+    RulePriority,
+}
+
+impl Expr {
+    /// Evaluate an expression using a knowledge base and a reduction strategy.
+    ///
+    /// This combines reductions and inlining of all symbols.
+    pub fn eval_with(&self, knowledge: &[Knowledge], strategy: Strategy) -> Result<Expr, Error> {
+        self.eval_with_env(knowledge, &Env::new(knowledge), strategy)
+    }
+
+    /// Evaluate an expression in an environment, using the standard strategy.
+    pub(crate) fn eval_env(&self, knowledge: &[Knowledge], env: &Env) -> Result<Expr, Error> {
+        self.eval_with_env(knowledge, env, Strategy::Standard)
+    }
+
+    fn eval_with_env(
+        &self,
+        knowledge: &[Knowledge],
+        env: &Env,
+        strategy: Strategy
+    ) -> Result<Expr, Error> {
+        let mut me = self.clone();
+        loop {
+            let expr = me.reduce_all_with_env(knowledge, env, strategy).inline_all(knowledge)?;
+            if expr == me {break};
+            me = expr;
+        }
+        Ok(me)
+    }
+
+    /// Reduces an expression using a knowledge base and a reduction strategy,
+    /// until it can not be reduced further.
+    pub fn reduce_all_with(&self, knowledge: &[Knowledge], strategy: Strategy) -> Expr {
+        self.reduce_all_with_env(knowledge, &Env::new(knowledge), strategy)
+    }
+
+    fn reduce_all_with_env(
+        &self,
+        knowledge: &[Knowledge],
+        env: &Env,
+        strategy: Strategy
+    ) -> Expr {
+        let mut me = self.clone();
+        while let Ok((expr, _)) = me.reduce_with_env(knowledge, env, strategy) {me = expr}
+        me
+    }
+
+    /// Reduces expression one step using a knowledge base and a reduction strategy.
+    pub fn reduce_with(
+        &self,
+        knowledge: &[Knowledge],
+        strategy: Strategy
+    ) -> Result<(Expr, usize), Error> {
+        self.reduce_with_env(knowledge, &Env::new(knowledge), strategy)
+    }
+
+    fn reduce_with_env(
+        &self,
+        knowledge: &[Knowledge],
+        env: &Env,
+        strategy: Strategy
+    ) -> Result<(Expr, usize), Error> {
+        use Strategy::*;
+
+        match strategy {
+            Standard => self.reduce_env(knowledge, env),
+            LeftmostInnermost => {
+                self.reduce_first_child(knowledge, env, strategy)
+                    .or_else(|_| self.reduce_root_env(knowledge, env))
+            }
+            LeftmostOutermost => {
+                self.reduce_root_env(knowledge, env)
+                    .or_else(|_| self.reduce_first_child(knowledge, env, strategy))
+            }
+            ParallelOutermost => {
+                self.reduce_root_env(knowledge, env)
+                    .or_else(|_| self.reduce_all_children(knowledge, env, strategy))
+            }
+            RulePriority => {
+                self.reduce_lowest_rule(knowledge, env).ok_or(Error::NoReductionRule)
+            }
+        }
+    }
+
+    /// Reduces the first child that can be reduced, from left to right.
+    fn reduce_first_child(
+        &self,
+        knowledge: &[Knowledge],
+        env: &Env,
+        strategy: Strategy
+    ) -> Result<(Expr, usize), Error> {
+        match self {
+            Op(op, a, b) => {
+                if let Ok((a, i)) = a.reduce_with_env(knowledge, env, strategy) {
+                    return Ok((Op(*op, Box::new(a), b.clone()), i));
+                }
+                if let Ok((b, i)) = b.reduce_with_env(knowledge, env, strategy) {
+                    return Ok((Op(*op, a.clone(), Box::new(b)), i));
+                }
+            }
+            Tup(items) | List(items) => {
+                for i in 0..items.len() {
+                    if let Ok((n, j)) = items[i].reduce_with_env(knowledge, env, strategy) {
+                        let mut items = items.clone();
+                        items[i] = n;
+                        return Ok((self.with_items(items), j));
+                    }
+                }
+            }
+            Sym(_) | Ret(_) => {}
+        }
+        Err(Error::NoReductionRule)
+    }
+
+    /// Reduces all children that can be reduced.
+    fn reduce_all_children(
+        &self,
+        knowledge: &[Knowledge],
+        env: &Env,
+        strategy: Strategy
+    ) -> Result<(Expr, usize), Error> {
+        let mut first: Option<usize> = None;
+        let mut reduce = |e: &Expr| -> Expr {
+            match e.reduce_with_env(knowledge, env, strategy) {
+                Ok((e, i)) => {
+                    if first.is_none() {first = Some(i)};
+                    e
+                }
+                Err(_) => e.clone(),
+            }
+        };
+        let expr = match self {
+            Op(op, a, b) => {
+                let a = reduce(a);
+                Op(*op, Box::new(a), Box::new(reduce(b)))
+            }
+            Tup(items) | List(items) => self.with_items(items.iter().map(reduce).collect()),
+            Sym(_) | Ret(_) => self.clone(),
+        };
+        first.map(|i| (expr, i)).ok_or(Error::NoReductionRule)
+    }
+
+    /// Reduces the sub-expression that matches the rule with the lowest index.
+    fn reduce_lowest_rule(&self, knowledge: &[Knowledge], env: &Env) -> Option<(Expr, usize)> {
+        let mut best = self.reduce_root_env(knowledge, env).ok();
+        let mut consider = |n: Option<(Expr, usize)>, f: &dyn Fn(Expr) -> Expr| {
+            if let Some((n, i)) = n {
+                if best.as_ref().map(|(_, j)| i < *j).unwrap_or(true) {
+                    best = Some((f(n), i));
+                }
+            }
+        };
+        match self {
+            Op(op, a, b) => {
+                consider(a.reduce_lowest_rule(knowledge, env),
+                         &|a| Op(*op, Box::new(a), b.clone()));
+                consider(b.reduce_lowest_rule(knowledge, env),
+                         &|b| Op(*op, a.clone(), Box::new(b)));
+            }
+            Tup(items) | List(items) => {
+                for i in 0..items.len() {
+                    consider(items[i].reduce_lowest_rule(knowledge, env), &|n| {
+                        let mut items = items.clone();
+                        items[i] = n;
+                        self.with_items(items)
+                    });
+                }
+            }
+            Sym(_) | Ret(_) => {}
+        }
+        best
+    }
+
+    /// Creates a tuple or list of the same kind as this expression.
+    fn with_items(&self, items: Vec<Expr>) -> Expr {
+        if let List(_) = self {List(items)} else {Tup(items)}
+    }
+}
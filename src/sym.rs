@@ -3,7 +3,8 @@ use std::fmt;
 use super::Expr;
 
 /// Contains symbols and operators on symbols.
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, PartialOrd, Debug, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Symbol {
     /// The wildcard symbol `_`.
     Any,
@@ -42,6 +43,19 @@ pub enum Symbol {
     UnopRetVar(Arc<String>, Box<Symbol>),
     /// A function without domain constraints.
     NoConstrVar(Arc<String>),
+    /// A higher-order pattern variable applied to distinct variables, e.g. `$F(x, y)`.
+    ///
+    /// This matches any expression and binds the function to a context,
+    /// where the values of the variables are holes.
+    /// The variables must be bound elsewhere in the pattern, before or after.
+    /// On the right side, the holes are filled with the values of the variables.
+    HoVar(Arc<String>, Vec<Arc<String>>),
+    /// A hole in a context bound by a higher-order pattern variable.
+    Hole(usize),
+    /// A metavariable in a query, e.g. `?h`.
+    ///
+    /// This stands for an unknown expression that a solver finds.
+    MetaVar(Arc<String>),
     /// `\false` for one argument.
     False1,
     /// `not`.
@@ -257,6 +271,16 @@ impl fmt::Display for Symbol {
             Rty => write!(w, "rty")?,
             VecOp => write!(w, "vec_op")?,
             Var(x) | NoConstrVar(x) => write!(w, "{}", x)?,
+            HoVar(f, args) => {
+                write!(w, "${}(", f)?;
+                for (i, x) in args.iter().enumerate() {
+                    if i > 0 {write!(w, ", ")?}
+                    write!(w, "{}", x)?;
+                }
+                write!(w, ")")?
+            }
+            Hole(i) => write!(w, "#{}", i)?,
+            MetaVar(x) => write!(w, "?{}", x)?,
             RetVar(x) => write!(w, "\\{}", x)?,
             ListVar(x) => write!(w, "[{}..]", x)?,
             Singleton(x) => write!(w, "[{}]", x)?,
@@ -285,6 +309,7 @@ impl fmt::Display for Symbol {
                 match **f {
                     Neg => write!(w, "-{}", x)?,
                     Len => write!(w, "compute::len({})", x)?,
+                    Ex => write!(w, "compute::ex({})", x)?,
                     _ => write!(w, "{:?}", self)?,
                 }
             }
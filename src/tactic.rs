@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use super::*;
+
+/// Refers to a rule in a knowledge base.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RuleRef {
+    /// The rule at some index.
+    Index(usize),
+    /// A rule that is looked up in the knowledge base.
+    ///
+    /// This keeps proofs stable when the order of rules changes.
+    Rule(Knowledge),
+}
+
+impl RuleRef {
+    /// Returns the index of the rule in a knowledge base.
+    pub fn index(&self, knowledge: &[Knowledge]) -> Result<usize, Error> {
+        match self {
+            RuleRef::Index(i) if *i < knowledge.len() => Ok(*i),
+            RuleRef::Index(_) => Err(Error::UnknownRule),
+            RuleRef::Rule(k) => knowledge.iter().position(|x| x == k).ok_or(Error::UnknownRule),
+        }
+    }
+}
+
+/// The maximum number of iterations of `Tactic::Repeat`.
+pub const REPEAT_LIMIT: usize = 1000;
+
+/// A tactic for guiding a proof.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Tactic {
+    /// Reduces one step using `Expr::reduce`.
+    Reduce,
+    /// Rewrites using a rule in some direction.
+    ///
+    /// Without a position, the first position where the rule applies is used,
+    /// with parents before children.
+    Rewrite(RuleRef, Dir, Option<Pos>),
+    /// Inlines the definition of a symbol.
+    Inline(Symbol),
+    /// Replaces a closed boolean expression with its normal form, see `boolean_normal_form`.
+    Decide,
+    /// Applies a tactic until it fails, makes no progress or returns to an earlier expression.
+    ///
+    /// Steps that return to an earlier expression are removed from the trace.
+    /// Returns `Error::NoProgress` after `REPEAT_LIMIT` iterations.
+    Repeat(Box<Tactic>),
+    /// Applies the first tactic that succeeds.
+    First(Vec<Tactic>),
+    /// Applies a tactic, or does nothing if it fails.
+    Try(Box<Tactic>),
+    /// Applies one tactic, then another.
+    Then(Box<Tactic>, Box<Tactic>),
+}
+
+/// Describes why a step in a proof is valid.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Justification {
+    /// One reduction step with the rule.
+    Reduce(Knowledge),
+    /// The rule was used in some direction at a position.
+    Rewrite(Knowledge, Dir, Pos),
+    /// The definition of the symbol was inlined.
+    Inline(Symbol),
+    /// Both expressions compute the same boolean function, see `boolean_eqv`.
+    Decide,
+}
+
+impl fmt::Display for Justification {
+    fn fmt(&self, w: &mut fmt::Formatter<'_>) -> std::result::Result<(), fmt::Error> {
+        match self {
+            Justification::Reduce(k) => write!(w, "( {} )", k),
+            Justification::Rewrite(k, Dir::Forward, pos) => write!(w, "( {} ) at {}", k, pos),
+            Justification::Rewrite(k, Dir::Backward, pos) => write!(w, "<- ( {} ) at {}", k, pos),
+            Justification::Inline(sym) => write!(w, "( inline {} )", sym),
+            Justification::Decide => write!(w, "( decide )"),
+        }
+    }
+}
+
+/// A proof step.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Step {
+    /// The expression after the step.
+    pub expr: Expr,
+    /// Why the step is valid.
+    pub justification: Justification,
+    /// The condition that was discharged, when using a conditional reduction.
+    pub condition: Option<Expr>,
+}
+
+/// The steps taken by a tactic, starting from a goal.
+#[derive(Clone, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trace {
+    /// The goal.
+    pub start: Expr,
+    /// The steps taken.
+    pub steps: Vec<Step>,
+}
+
+impl Trace {
+    /// Returns the expression at the end of the trace.
+    pub fn result(&self) -> &Expr {
+        self.steps.last().map(|step| &step.expr).unwrap_or(&self.start)
+    }
+
+    // Returns the expression after some number of steps.
+    fn expr_at(&self, n: usize) -> &Expr {
+        if n == 0 {&self.start} else {&self.steps[n - 1].expr}
+    }
+
+    /// Checks that every step follows from the expression before it.
+    ///
+    /// Reductions, rewrites and inlining are done again,
+    /// and boolean normal forms are compared again.
+    /// Returns `Error::InvalidStep` with the index of the first step that does not follow.
+    pub fn check(&self, knowledge: &[Knowledge]) -> Result<(), Error> {
+        let mut prev = &self.start;
+        for (i, step) in self.steps.iter().enumerate() {
+            let valid = match &step.justification {
+                Justification::Reduce(k) => prev.redexes(knowledge)
+                    .any(|r| &knowledge[r.rule] == k && r.expr == step.expr),
+                Justification::Rewrite(k, dir, pos) => {
+                    let j = RuleRef::Rule(k.clone()).index(knowledge)?;
+                    prev.rewrite_at(knowledge, pos, j, *dir).as_ref() == Ok(&step.expr)
+                }
+                Justification::Inline(sym) => {
+                    prev.inline(sym, knowledge).as_ref() == Ok(&step.expr)
+                }
+                Justification::Decide => boolean_eqv(prev, &step.expr, knowledge).unwrap_or(false),
+            };
+            if !valid {return Err(Error::InvalidStep(i))};
+            prev = &step.expr;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, w: &mut fmt::Formatter<'_>) -> std::result::Result<(), fmt::Error> {
+        writeln!(w, "{}", self.start)?;
+        for step in &self.steps {
+            write!(w, "{}\t\t\t{}", step.expr, step.justification)?;
+            if let Some(cond) = &step.condition {write!(w, " when {}", cond)?}
+            writeln!(w)?;
+        }
+        Ok(())
+    }
+}
+
+impl Tactic {
+    /// Runs the tactic against a goal, producing a proof trace.
+    pub fn run(&self, goal: &Expr, knowledge: &[Knowledge]) -> Result<Trace, Error> {
+        let mut trace = Trace {start: goal.clone(), steps: vec![]};
+        self.apply(&mut trace, knowledge)?;
+        Ok(trace)
+    }
+
+    /// Applies the tactic to the end of a trace.
+    ///
+    /// When the tactic fails, the trace is left unchanged.
+    fn apply(&self, trace: &mut Trace, knowledge: &[Knowledge]) -> Result<(), Error> {
+        let n = trace.steps.len();
+        let res = self.apply_steps(trace, knowledge);
+        if res.is_err() {trace.steps.truncate(n)};
+        res
+    }
+
+    fn apply_steps(&self, trace: &mut Trace, knowledge: &[Knowledge]) -> Result<(), Error> {
+        use Tactic::*;
+
+        let (expr, justification, condition) = match self {
+            Reduce => {
+                let goal = trace.result();
+                let (expr, i) = goal.reduce(knowledge)?;
+                // Finds the position of the reduction to get the discharged condition.
+                let condition = if let CondRed(..) = knowledge[i] {
+                    let env = Env::new(knowledge);
+                    goal.positions().into_iter()
+                        .find(|pos| {
+                            goal.rewrite_at_env(knowledge, &env, pos, i, Dir::Forward).as_ref() ==
+                                Ok(&expr)
+                        })
+                        .and_then(|pos| goal.condition_at(knowledge, &pos, i))
+                } else {None};
+                (expr, Justification::Reduce(knowledge[i].clone()), condition)
+            }
+            Rewrite(rule, dir, pos) => {
+                let i = rule.index(knowledge)?;
+                let goal = trace.result();
+                let (expr, pos) = match pos {
+                    Some(pos) => (goal.rewrite_at(knowledge, pos, i, *dir)?, pos.clone()),
+                    None => {
+                        let env = Env::new(knowledge);
+                        goal.positions().into_iter()
+                            .find_map(|pos| goal.rewrite_at_env(knowledge, &env, &pos, i, *dir).ok()
+                                .map(|expr| (expr, pos)))
+                            .ok_or(Error::NoReductionRule)?
+                    }
+                };
+                let condition = goal.condition_at(knowledge, &pos, i);
+                (expr, Justification::Rewrite(knowledge[i].clone(), *dir, pos), condition)
+            }
+            Inline(sym) => {
+                let expr = trace.result().inline(sym, knowledge)?;
+                if &expr == trace.result() {return Err(Error::NoProgress)};
+                (expr, Justification::Inline(sym.clone()), None)
+            }
+            Decide => {
+                let expr = boolean_normal_form(trace.result(), knowledge)?;
+                if &expr == trace.result() {return Err(Error::NoProgress)};
+                (expr, Justification::Decide, None)
+            }
+            Repeat(tactic) => {
+                // Expressions in the trace by hash, with the number of steps before them.
+                let mut seen: HashMap<u64, Vec<usize>> = HashMap::new();
+                seen.entry(trace.result().hash_u64()).or_default().push(trace.steps.len());
+                for _ in 0..REPEAT_LIMIT {
+                    let n = trace.steps.len();
+                    if tactic.apply(trace, knowledge).is_err() || trace.steps.len() == n {
+                        return Ok(());
+                    }
+                    // Removes the steps of a cycle back to an earlier expression.
+                    let h = trace.result().hash_u64();
+                    let earlier = seen.get(&h).and_then(|ms| ms.iter().copied()
+                        .find(|&m| trace.expr_at(m) == trace.result()));
+                    if let Some(m) = earlier {
+                        trace.steps.truncate(m);
+                        return Ok(());
+                    }
+                    seen.entry(h).or_default().push(trace.steps.len());
+                }
+                return Err(Error::NoProgress);
+            }
+            First(tactics) => {
+                for tactic in tactics {
+                    if tactic.apply(trace, knowledge).is_ok() {return Ok(())};
+                }
+                return Err(Error::NoProgress);
+            }
+            Try(tactic) => {
+                let _ = tactic.apply(trace, knowledge);
+                return Ok(());
+            }
+            Then(a, b) => {
+                a.apply(trace, knowledge)?;
+                return b.apply(trace, knowledge);
+            }
+        };
+        trace.steps.push(Step {expr, justification, condition});
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Tactic {
+    type Err = String;
+
+    /// Parses a tactic script, see `parse_tactic_str`.
+    fn from_str(s: &str) -> Result<Tactic, String> {parse_tactic_str(s)}
+}
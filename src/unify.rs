@@ -0,0 +1,117 @@
+use super::*;
+
+/// Returns the name of a symbol when it is a variable in unification.
+type IsVar = fn(&Symbol) -> Option<&Arc<String>>;
+
+fn meta_var(s: &Symbol) -> Option<&Arc<String>> {
+    if let MetaVar(x) = s {Some(x)} else {None}
+}
+
+fn var_or_meta_var(s: &Symbol) -> Option<&Arc<String>> {
+    if let Var(x) | MetaVar(x) = s {Some(x)} else {None}
+}
+
+/// Finds the most general unifier of two expressions,
+/// where variables and metavariables on both sides can be bound.
+///
+/// A variable and a metavariable with the same name are the same variable,
+/// and `_` unifies with anything.
+/// Returns the bindings, where the values contain no bound variables,
+/// or `None` when the expressions do not unify, e.g. `x` and `not(x)`.
+pub fn unify(a: &Expr, b: &Expr) -> Option<Vec<(Arc<String>, Expr)>> {
+    let mut ctx = Context::new();
+    if !ctx.unify_vars(a, b) {return None};
+    Some(ctx.vars.iter()
+        .map(|(x, a)| (x.clone(), ctx.substitute_unified(a)))
+        .collect())
+}
+
+impl Context {
+    /// Unifies two expressions, binding metavariables on both sides.
+    ///
+    /// The bindings are stored in `vars`, where a value might contain bound metavariables.
+    /// Other symbols must be equal on both sides.
+    /// Fails when a metavariable would be bound to an expression containing itself.
+    pub fn unify(&mut self, a: &Expr, b: &Expr) -> bool {
+        self.unify_with(a, b, meta_var)
+    }
+
+    /// Unifies two expressions, binding variables and metavariables on both sides.
+    ///
+    /// See `unify`.
+    pub fn unify_vars(&mut self, a: &Expr, b: &Expr) -> bool {
+        self.unify_with(a, b, var_or_meta_var)
+    }
+
+    /// Replaces metavariables bound by `Context::unify` with their values.
+    pub fn substitute_metas(&self, a: &Expr) -> Expr {
+        self.substitute_with(a, meta_var)
+    }
+
+    /// Replaces variables bound by `Context::unify_vars` with their values.
+    pub fn substitute_unified(&self, a: &Expr) -> Expr {
+        self.substitute_with(a, var_or_meta_var)
+    }
+
+    fn unify_with(&mut self, a: &Expr, b: &Expr, is_var: IsVar) -> bool {
+        let n = self.vars.len();
+        if self.try_unify(a, b, is_var) {true} else {
+            self.vars.truncate(n);
+            false
+        }
+    }
+
+    fn try_unify(&mut self, a: &Expr, b: &Expr, is_var: IsVar) -> bool {
+        let (a, b) = (self.resolve(a, is_var), self.resolve(b, is_var));
+        if let (Sym(Any), _) | (_, Sym(Any)) = (&a, &b) {return true};
+        let var = |a: &Expr| if let Sym(s) = a {is_var(s).cloned()} else {None};
+        match (var(&a), var(&b)) {
+            (Some(x), Some(y)) if x == y => true,
+            (Some(x), _) => self.bind_unified(x, &b, is_var),
+            (_, Some(y)) => self.bind_unified(y, &a, is_var),
+            _ => match (&a, &b) {
+                (Op(op1, a1, b1), Op(op2, a2, b2)) =>
+                    op1 == op2 && self.try_unify(a1, a2, is_var) && self.try_unify(b1, b2, is_var),
+                (Tup(a), Tup(b)) | (List(a), List(b)) => a.len() == b.len() &&
+                    a.iter().zip(b).all(|(a, b)| self.try_unify(a, b, is_var)),
+                _ => a == b,
+            }
+        }
+    }
+
+    fn bind_unified(&mut self, x: Arc<String>, a: &Expr, is_var: IsVar) -> bool {
+        if self.occurs(&x, a, is_var) {return false};
+        self.vars.push((x, a.clone()));
+        true
+    }
+
+    /// Follows the bindings of a variable.
+    fn resolve(&self, a: &Expr, is_var: IsVar) -> Expr {
+        let mut a = a;
+        while let Some(x) = if let Sym(s) = a {is_var(s)} else {None} {
+            match self.vars.iter().rev().find(|(y, _)| y == x) {
+                Some((_, b)) => a = b,
+                None => break,
+            }
+        }
+        a.clone()
+    }
+
+    fn occurs(&self, x: &Arc<String>, a: &Expr, is_var: IsVar) -> bool {
+        match self.resolve(a, is_var) {
+            Sym(s) => is_var(&s) == Some(x),
+            a => a.children().into_iter().any(|b| self.occurs(x, b, is_var)),
+        }
+    }
+
+    fn substitute_with(&self, a: &Expr, is_var: IsVar) -> Expr {
+        let f = |a: &Expr| self.substitute_with(a, is_var);
+        match self.resolve(a, is_var) {
+            Sym(x) => Sym(x),
+            Ret(x) => Ret(x),
+            Op(op, a, b) => Op(op, Box::new(f(&a)), Box::new(f(&b))),
+            Tup(items) => Tup(items.iter().map(f).collect()),
+            List(items) => List(items.iter().map(f).collect()),
+        }
+    }
+}
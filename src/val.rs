@@ -1,7 +1,9 @@
 use std::fmt;
+use std::hash::{Hash, Hasher};
 
 /// Value.
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, PartialOrd, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value {
     /// A boolean value.
     Bool(bool),
@@ -9,6 +11,40 @@ pub enum Value {
     F64(f64),
 }
 
+impl Value {
+    /// Returns `true` if the values are the same, comparing numbers by bits.
+    ///
+    /// Unlike `==`, this is reflexive: Every NaN is the same as any other NaN.
+    /// Like `==`, `0.0` is the same as `-0.0`.
+    pub fn same(&self, other: &Value) -> bool {
+        use Value::*;
+
+        match (self, other) {
+            (Bool(a), Bool(b)) => a == b,
+            (F64(a), F64(b)) => bits(*a) == bits(*b),
+            _ => false,
+        }
+    }
+}
+
+// Normalizes `-0.0` and NaN, such that values that are equal have the same bits.
+fn bits(v: f64) -> u64 {
+    if v == 0.0 {0.0f64.to_bits()}
+    else if v.is_nan() {f64::NAN.to_bits()}
+    else {v.to_bits()}
+}
+
+impl Hash for Value {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        use Value::*;
+
+        match self {
+            Bool(v) => {0.hash(state); v.hash(state)}
+            F64(v) => {1.hash(state); bits(*v).hash(state)}
+        }
+    }
+}
+
 impl fmt::Display for Value {
     fn fmt(&self, w: &mut fmt::Formatter<'_>) -> std::result::Result<(), fmt::Error> {
         use Value::*;